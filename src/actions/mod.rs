@@ -5,6 +5,7 @@ pub mod utils;
 
 use std::{borrow::Cow, str::FromStr, sync::OnceLock};
 
+use crate::paper::trading_prefix;
 use crate::store::{LocalStore, Store, StoreMap};
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
@@ -74,7 +75,7 @@ impl AgentActionLog {
 
     fn new() -> Self {
         Self {
-            actions: LocalStore::open_map(Self::ACTIONS_PREFIX),
+            actions: LocalStore::open_map(trading_prefix(Self::ACTIONS_PREFIX)),
         }
     }
 }
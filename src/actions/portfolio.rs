@@ -3,6 +3,12 @@ use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 
 use super::{Action, ActionLog};
+use crate::exit::ExitReason;
+use crate::fill::SwapFill;
+use crate::orders::OrderSide;
+use crate::reconcile::HoldingDrift;
+use crate::risk::RiskDecision;
+use crate::swap::params::SwapSettings;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PortfolioAction {
@@ -11,18 +17,90 @@ pub enum PortfolioAction {
         amount: u64,
         sol: u64,
         tx_sig: String,
+        /// Fill read from the confirmed transaction
+        #[serde(default)]
+        fill: Option<SwapFill>,
+        /// Slippage tolerance and priority fee the swap was sent with
+        #[serde(default)]
+        settings: Option<SwapSettings>,
     },
     Sell {
         token: String,
         amount: u64,
         sol: u64,
         tx_sig: String,
+        /// Fill read from the confirmed transaction
+        #[serde(default)]
+        fill: Option<SwapFill>,
+        /// Slippage tolerance and priority fee the swap was sent with
+        #[serde(default)]
+        settings: Option<SwapSettings>,
     },
     Pnl {
         token: String,
         pnl: f64,
         sell_action: ActionLog,
     },
+    Risk {
+        token: String,
+        decision: RiskDecision,
+        requested_sol: u64,
+        approved_sol: u64,
+        reason: String,
+    },
+    Exit {
+        token: String,
+        reason: ExitReason,
+        price_in_sol: f64,
+        profit_rate: f64,
+    },
+    Buyback {
+        period_start: u64,
+        period_end: u64,
+        realized_pnl: f64,
+        sol: u64,
+        jimmy_amount: u64,
+        tx_sig: String,
+        burn_tx_sig: Option<String>,
+    },
+    Reconcile {
+        drifts: Vec<HoldingDrift>,
+        /// Mints in the wallet no holding tracks
+        untracked: Vec<String>,
+        fixed: bool,
+    },
+    Order {
+        token: String,
+        order_id: u64,
+        side: OrderSide,
+        limit_price: f64,
+        price_in_sol: f64,
+    },
+    /// A buy split into slices over a window
+    Twap {
+        token: String,
+        order_id: u64,
+        sol: u64,
+        slices: usize,
+        window_secs: u64,
+        /// Round the order was scheduled by
+        #[serde(default)]
+        round_id: Option<u64>,
+    },
+    /// A slice of a TWAP order sent, its buy is logged on its own
+    TwapSlice {
+        token: String,
+        order_id: u64,
+        slice: usize,
+        sol: u64,
+        /// Signature of the buy of the slice, if filled
+        #[serde(default)]
+        tx_sig: Option<String>,
+        error: Option<String>,
+        /// Round the order was scheduled by
+        #[serde(default)]
+        round_id: Option<u64>,
+    },
 }
 
 impl PortfolioAction {
@@ -32,6 +110,8 @@ impl PortfolioAction {
             amount,
             sol,
             tx_sig,
+            fill: None,
+            settings: None,
         }
     }
 
@@ -41,6 +121,33 @@ impl PortfolioAction {
             amount,
             sol,
             tx_sig,
+            fill: None,
+            settings: None,
+        }
+    }
+
+    /// Attaches the fill of a buy or a sell.
+    pub fn with_fill(mut self, swap_fill: Option<SwapFill>) -> Self {
+        if let PortfolioAction::Buy { fill, .. } | PortfolioAction::Sell { fill, .. } = &mut self {
+            *fill = swap_fill;
+        }
+        self
+    }
+
+    /// Attaches the settings a buy or a sell was sent with.
+    pub fn with_settings(mut self, swap_settings: Option<SwapSettings>) -> Self {
+        if let PortfolioAction::Buy { settings, .. } | PortfolioAction::Sell { settings, .. } =
+            &mut self
+        {
+            *settings = swap_settings;
+        }
+        self
+    }
+
+    pub fn fill(&self) -> Option<&SwapFill> {
+        match self {
+            PortfolioAction::Buy { fill, .. } | PortfolioAction::Sell { fill, .. } => fill.as_ref(),
+            _ => None,
         }
     }
 
@@ -51,6 +158,131 @@ impl PortfolioAction {
             sell_action,
         }
     }
+
+    pub fn risk(
+        token: String,
+        decision: RiskDecision,
+        requested_sol: u64,
+        approved_sol: u64,
+        reason: String,
+    ) -> Self {
+        PortfolioAction::Risk {
+            token,
+            decision,
+            requested_sol,
+            approved_sol,
+            reason,
+        }
+    }
+
+    pub fn exit(token: String, reason: ExitReason, price_in_sol: f64, profit_rate: f64) -> Self {
+        PortfolioAction::Exit {
+            token,
+            reason,
+            price_in_sol,
+            profit_rate,
+        }
+    }
+
+    pub fn order(
+        token: String,
+        order_id: u64,
+        side: OrderSide,
+        limit_price: f64,
+        price_in_sol: f64,
+    ) -> Self {
+        PortfolioAction::Order {
+            token,
+            order_id,
+            side,
+            limit_price,
+            price_in_sol,
+        }
+    }
+
+    pub fn twap(
+        token: String,
+        order_id: u64,
+        sol: u64,
+        slices: usize,
+        window_secs: u64,
+        round_id: Option<u64>,
+    ) -> Self {
+        PortfolioAction::Twap {
+            token,
+            order_id,
+            sol,
+            slices,
+            window_secs,
+            round_id,
+        }
+    }
+
+    pub fn twap_slice(
+        token: String,
+        order_id: u64,
+        slice: usize,
+        sol: u64,
+        tx_sig: Option<String>,
+        error: Option<String>,
+        round_id: Option<u64>,
+    ) -> Self {
+        PortfolioAction::TwapSlice {
+            token,
+            order_id,
+            slice,
+            sol,
+            tx_sig,
+            error,
+            round_id,
+        }
+    }
+
+    pub fn buyback(
+        period_start: u64,
+        period_end: u64,
+        realized_pnl: f64,
+        sol: u64,
+        jimmy_amount: u64,
+        tx_sig: String,
+        burn_tx_sig: Option<String>,
+    ) -> Self {
+        PortfolioAction::Buyback {
+            period_start,
+            period_end,
+            realized_pnl,
+            sol,
+            jimmy_amount,
+            tx_sig,
+            burn_tx_sig,
+        }
+    }
+
+    pub fn reconcile(drifts: Vec<HoldingDrift>, untracked: Vec<String>, fixed: bool) -> Self {
+        PortfolioAction::Reconcile {
+            drifts,
+            untracked,
+            fixed,
+        }
+    }
+}
+
+fn swap_prompt(fill: &Option<SwapFill>, settings: &Option<SwapSettings>) -> String {
+    let mut prompt = String::new();
+    if let Some(settings) = settings {
+        prompt += &format!(
+            ", sent with a {} bps slippage tolerance at {} micro-lamports per compute unit",
+            settings.slippage_bps, settings.compute_unit_price
+        );
+    }
+    if let Some(fill) = fill {
+        prompt += &format!(
+            ", paying {} lamports of fees with {:.2}% slippage against the quote",
+            fill.fee_lamports,
+            fill.slippage() * 100.0
+        );
+    }
+    prompt
 }
 
 impl ToString for PortfolioAction {
@@ -75,16 +307,22 @@ impl Action for PortfolioAction {
                 amount,
                 sol,
                 tx_sig,
+                fill,
+                settings,
             } => {
-                format!("Buy {amount} tokens of {token} with {sol} SOL(LAMPORT) which tx signature is {tx_sig}")
+                let swap = swap_prompt(fill, settings);
+                format!("Buy {amount} tokens of {token} with {sol} SOL(LAMPORT) which tx signature is {tx_sig}{swap}")
             }
             PortfolioAction::Sell {
                 token,
                 amount,
                 sol,
                 tx_sig,
+                fill,
+                settings,
             } => {
-                format!("Sell {amount} tokens of {token} with {sol} SOL(LAMPORT) which tx signature is {tx_sig}")
+                let swap = swap_prompt(fill, settings);
+                format!("Sell {amount} tokens of {token} with {sol} SOL(LAMPORT) which tx signature is {tx_sig}{swap}")
             }
             PortfolioAction::Pnl {
                 token,
@@ -96,6 +334,86 @@ impl Action for PortfolioAction {
                     .prompt();
                 format!("Realize PnL of {pnl} SOL(LAMPORT) from token {token} from sell action \"{action}\"", )
             }
+            PortfolioAction::Risk {
+                token,
+                decision,
+                requested_sol,
+                approved_sol,
+                reason,
+            } => {
+                format!("Risk manager {decision} the buy of token {token} from {requested_sol} to {approved_sol} SOL(LAMPORT) because of {reason}")
+            }
+            PortfolioAction::Exit {
+                token,
+                reason,
+                price_in_sol,
+                profit_rate,
+            } => {
+                format!("Exit monitor triggered the {reason} of token {token} at {price_in_sol} SOL with a profit rate of {profit_rate}")
+            }
+            PortfolioAction::Buyback {
+                period_start,
+                period_end,
+                realized_pnl,
+                sol,
+                jimmy_amount,
+                tx_sig,
+                burn_tx_sig,
+            } => {
+                let burn = match burn_tx_sig {
+                    Some(burn_tx_sig) => {
+                        format!(", then burn them with tx signature {burn_tx_sig}")
+                    }
+                    None => String::new(),
+                };
+                format!("Buy back {jimmy_amount} JIMMY with {sol} SOL(LAMPORT) out of {realized_pnl} SOL(LAMPORT) realized PnL between {period_start} and {period_end} which tx signature is {tx_sig}{burn}")
+            }
+            PortfolioAction::Reconcile {
+                drifts,
+                untracked,
+                fixed,
+            } => {
+                let drifts = drifts
+                    .iter()
+                    .map(|drift| drift.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let fixed = if *fixed { "fixed" } else { "only reported" };
+                format!("Reconcile the holdings with the wallet, drifts [{drifts}] and untracked tokens [{}] were {fixed}", untracked.join(", "))
+            }
+            PortfolioAction::Order {
+                token,
+                order_id,
+                side,
+                limit_price,
+                price_in_sol,
+            } => {
+                format!("Limit {side} order {order_id} of token {token} at {limit_price} SOL triggered at {price_in_sol} SOL")
+            }
+            PortfolioAction::Twap {
+                token,
+                order_id,
+                sol,
+                slices,
+                window_secs,
+                ..
+            } => {
+                format!("Schedule TWAP order {order_id} buying token {token} with {sol} SOL(LAMPORT) in {slices} slices over {window_secs} seconds")
+            }
+            PortfolioAction::TwapSlice {
+                token,
+                order_id,
+                slice,
+                sol,
+                error,
+                ..
+            } => {
+                let result = match error {
+                    Some(error) => format!("failed because of {error}"),
+                    None => "filled".to_string(),
+                };
+                format!("Slice {slice} of TWAP order {order_id} buying token {token} with {sol} SOL(LAMPORT) {result}")
+            }
         }
     }
 }
@@ -10,6 +10,10 @@ pub enum TwitterAction {
         tweet_id: String,
         tweet_text: String,
     },
+    Buyback {
+        tweet_id: String,
+        tweet_text: String,
+    },
 }
 
 impl ToString for TwitterAction {
@@ -38,6 +42,15 @@ impl Action for TwitterAction {
                     tweet_id, tweet_text
                 )
             }
+            TwitterAction::Buyback {
+                tweet_id,
+                tweet_text,
+            } => {
+                format!(
+                    "Post buyback record from tweet {} with the following text: {}",
+                    tweet_id, tweet_text
+                )
+            }
         }
     }
 }
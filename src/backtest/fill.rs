@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constant::*;
+
+/// Simulated execution costs of a swap.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FillModel {
+    /// Flat fee paid in lamports for every swap
+    pub fee_lamports: u64,
+    /// Price slippage against the swap in basis points
+    pub slippage_bps: u64,
+}
+
+impl FillModel {
+    pub fn new(fee_lamports: u64, slippage_bps: u64) -> Self {
+        Self {
+            fee_lamports,
+            slippage_bps,
+        }
+    }
+
+    fn slippage_factor(&self) -> f64 {
+        1.0 - self.slippage_bps as f64 / 10_000.0
+    }
+
+    /// Raw token amount received for spending `sol_amount` lamports.
+    ///
+    /// `price_in_sol` is the price of one UI unit of the token in SOL.
+    pub fn buy(&self, sol_amount: u64, price_in_sol: f64, decimals: u8) -> u64 {
+        if price_in_sol <= 0.0 {
+            return 0;
+        }
+
+        let sol_in = sol_amount.saturating_sub(self.fee_lamports) as f64 / LAMPORTS_PER_SOL as f64;
+        let ui_amount = sol_in / price_in_sol * self.slippage_factor();
+        (ui_amount * 10_f64.powi(decimals as i32)).floor() as u64
+    }
+
+    /// Lamports received for selling `token_amount` raw tokens.
+    ///
+    /// `price_in_sol` is the price of one UI unit of the token in SOL.
+    pub fn sell(&self, token_amount: u64, price_in_sol: f64, decimals: u8) -> u64 {
+        let ui_amount = token_amount as f64 / 10_f64.powi(decimals as i32);
+        let sol_out = ui_amount * price_in_sol * self.slippage_factor();
+        ((sol_out * LAMPORTS_PER_SOL as f64).floor() as u64).saturating_sub(self.fee_lamports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_costs() {
+        let model = FillModel::new(0, 0);
+        // 1 SOL at 0.5 SOL per token
+        assert_eq!(model.buy(LAMPORTS_PER_SOL, 0.5, 6), 2_000_000);
+        assert_eq!(model.sell(2_000_000, 0.5, 6), LAMPORTS_PER_SOL);
+
+        // 1% slippage
+        let model = FillModel::new(0, 100);
+        assert!(model.buy(LAMPORTS_PER_SOL, 0.5, 6).abs_diff(1_980_000) <= 1);
+        assert!(model.sell(2_000_000, 0.5, 6).abs_diff(990_000_000) <= 1);
+
+        let model = FillModel::new(5_000, 0);
+        assert_eq!(model.sell(1_000_000, 1.0, 6), LAMPORTS_PER_SOL - 5_000);
+        assert_eq!(model.buy(1_000, 1.0, 6), 0);
+    }
+}
@@ -0,0 +1,482 @@
+//! Offline backtesting of trading strategies.
+//!
+//! The engine replays a [`Strategy`] day by day over recorded price histories
+//! and simulates the trading round of the pipeline: profitable holdings that
+//! are no longer selected are sold, then the daily budget is spent on the
+//! selected trades. No network access is needed, the histories are read from
+//! JSON fixtures (see `assets/backtest`).
+mod fill;
+
+pub use fill::FillModel;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::constant::*;
+use crate::cost_basis::CostBasis;
+use crate::indicators::max_drawdown;
+use crate::portfolio::OtherTokenHolding;
+use crate::price::coingecko::HistoricalPriceResponse;
+use crate::strategy::{CandidateData, Sizing, Strategy};
+use crate::token::structs::TokenInfo;
+
+const DAY_MS: f64 = 86_400_000.0;
+
+/// Recorded price history of a token.
+///
+/// Prices are in USD, timestamps in milliseconds, as returned by CoinGecko.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceFixture {
+    pub symbol: String,
+    pub name: String,
+    pub address: String,
+    pub decimals: u8,
+    pub coingecko_id: Option<String>,
+    pub history: HistoricalPriceResponse,
+}
+
+impl PriceFixture {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let fixture = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid fixture {}: {}", path.display(), e))?;
+        Ok(fixture)
+    }
+
+    pub fn token_info(&self) -> Result<TokenInfo> {
+        Ok(TokenInfo {
+            address: Pubkey::from_str(&self.address)?,
+            decimals: self.decimals,
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            coingecko_id: self.coingecko_id.clone(),
+        })
+    }
+
+    /// Latest price at or before `timestamp`.
+    fn price_at(&self, timestamp: f64) -> Option<f64> {
+        self.history
+            .prices
+            .iter()
+            .take_while(|p| p[0] <= timestamp)
+            .last()
+            .map(|p| p[1])
+    }
+
+    /// History of the `days` days up to `timestamp`.
+    fn window(&self, timestamp: f64, days: u32) -> HistoricalPriceResponse {
+        let start = timestamp - days as f64 * DAY_MS;
+        let slice = |series: &Vec<Vec<f64>>| {
+            series
+                .iter()
+                .filter(|p| p[0] >= start && p[0] <= timestamp)
+                .cloned()
+                .collect()
+        };
+
+        HistoricalPriceResponse {
+            prices: slice(&self.history.prices),
+            market_caps: slice(&self.history.market_caps),
+            total_volumes: slice(&self.history.total_volumes),
+        }
+    }
+}
+
+/// Loads every `*.json` fixture of a directory.
+///
+/// Returns the SOL fixture, which prices every other token in SOL, and the
+/// fixtures of the tradable tokens.
+pub fn load_fixtures(dir: impl AsRef<Path>) -> Result<(PriceFixture, Vec<PriceFixture>)> {
+    let mut sol = None;
+    let mut tokens = vec![];
+
+    let mut paths = std::fs::read_dir(dir.as_ref())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        let fixture = PriceFixture::load(&path)?;
+        if fixture.address == SOL_MINT {
+            sol = Some(fixture);
+        } else {
+            tokens.push(fixture);
+        }
+    }
+
+    let sol = sol.ok_or(anyhow::anyhow!(
+        "No SOL fixture found in {}",
+        dir.as_ref().display()
+    ))?;
+    Ok((sol, tokens))
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub initial_sol: f64,
+    pub daily_budget_sol: f64,
+    pub lookback_days: u32,
+    pub min_profit_rate: f64,
+    pub sizing: Sizing,
+    pub cost_basis: CostBasis,
+    pub fill_model: FillModel,
+}
+
+impl BacktestConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            initial_sol: config.backtest_initial_sol,
+            daily_budget_sol: config.max_sol_trading_amount_one_day,
+            lookback_days: config.strategy_lookback_days,
+            min_profit_rate: config.min_profit_rate,
+            sizing: Sizing::from_config(config),
+            cost_basis: config.cost_basis,
+            fill_model: FillModel::new(config.backtest_fee_lamports, config.backtest_slippage_bps),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityPoint {
+    /// UNIX timestamp in milliseconds
+    pub timestamp: u64,
+    /// Net asset value in lamports
+    pub nav: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub strategy: String,
+    pub initial_nav: f64,
+    pub equity_curve: Vec<EquityPoint>,
+    /// Realized PnL of the closed parts of positions, in lamports
+    pub realized_pnl: f64,
+    pub fees_paid: u64,
+    /// Total lamports bought and sold
+    pub traded_volume: u64,
+    pub trades: usize,
+}
+
+impl BacktestReport {
+    pub fn final_nav(&self) -> f64 {
+        self.equity_curve
+            .last()
+            .map(|p| p.nav)
+            .unwrap_or(self.initial_nav)
+    }
+
+    /// Total PnL in lamports
+    pub fn pnl(&self) -> f64 {
+        self.final_nav() - self.initial_nav
+    }
+
+    pub fn return_rate(&self) -> f64 {
+        if self.initial_nav == 0.0 {
+            return 0.0;
+        }
+
+        self.pnl() / self.initial_nav
+    }
+
+    /// Largest peak-to-trough decline of the equity curve, from 0 to 1.
+    pub fn max_drawdown(&self) -> f64 {
+        let navs = std::iter::once(self.initial_nav)
+            .chain(self.equity_curve.iter().map(|p| p.nav))
+            .collect::<Vec<_>>();
+        max_drawdown(&navs)
+    }
+
+    /// Traded volume relative to the average NAV.
+    pub fn turnover(&self) -> f64 {
+        if self.equity_curve.is_empty() {
+            return 0.0;
+        }
+
+        let avg_nav =
+            self.equity_curve.iter().map(|p| p.nav).sum::<f64>() / self.equity_curve.len() as f64;
+        if avg_nav == 0.0 {
+            return 0.0;
+        }
+
+        self.traded_volume as f64 / avg_nav
+    }
+}
+
+impl std::fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sol = |lamports: f64| lamports / LAMPORTS_PER_SOL as f64;
+        write!(
+            f,
+            "Backtest {}: days: {}, trades: {}, NAV: {:.4} -> {:.4} SOL, PnL: {:.4} SOL ({:.2}%), realized PnL: {:.4} SOL, max drawdown: {:.2}%, turnover: {:.2}, fees: {:.4} SOL",
+            self.strategy,
+            self.equity_curve.len(),
+            self.trades,
+            sol(self.initial_nav),
+            sol(self.final_nav()),
+            sol(self.pnl()),
+            self.return_rate() * 100.0,
+            sol(self.realized_pnl),
+            self.max_drawdown() * 100.0,
+            self.turnover(),
+            sol(self.fees_paid as f64),
+        )
+    }
+}
+
+/// Simulated counterpart of [`crate::portfolio::Portfolio`].
+struct SimPortfolio {
+    sol_balance: u64,
+    holdings: HashMap<Pubkey, OtherTokenHolding>,
+}
+
+impl SimPortfolio {
+    fn nav(&self, prices_in_sol: &HashMap<Pubkey, f64>) -> f64 {
+        let holdings_value: f64 = self
+            .holdings
+            .iter()
+            .map(|(mint, holding)| {
+                let price = prices_in_sol.get(mint).copied().unwrap_or_default();
+                holding.holding_ui_amount() * price * LAMPORTS_PER_SOL as f64
+            })
+            .sum();
+
+        self.sol_balance as f64 + holdings_value
+    }
+}
+
+pub struct Backtest<'a> {
+    config: BacktestConfig,
+    strategy: &'a dyn Strategy,
+}
+
+impl<'a> Backtest<'a> {
+    pub fn new(config: BacktestConfig, strategy: &'a dyn Strategy) -> Self {
+        Self { config, strategy }
+    }
+
+    pub fn run(&self, sol: &PriceFixture, tokens: &[PriceFixture]) -> Result<BacktestReport> {
+        let first = sol
+            .history
+            .prices
+            .first()
+            .ok_or(anyhow::anyhow!("Empty SOL price history"))?[0];
+        let last = sol.history.prices.last().unwrap()[0];
+
+        let token_infos = tokens
+            .iter()
+            .map(|fixture| fixture.token_info())
+            .collect::<Result<Vec<_>>>()?;
+
+        let fill_model = self.config.fill_model;
+        let initial_sol = (self.config.initial_sol * LAMPORTS_PER_SOL as f64) as u64;
+        let daily_budget = self.config.daily_budget_sol * LAMPORTS_PER_SOL as f64;
+
+        let mut portfolio = SimPortfolio {
+            sol_balance: initial_sol,
+            holdings: HashMap::new(),
+        };
+        let mut report = BacktestReport {
+            strategy: self.strategy.name().to_string(),
+            initial_nav: initial_sol as f64,
+            equity_curve: vec![],
+            realized_pnl: 0.0,
+            fees_paid: 0,
+            traded_volume: 0,
+            trades: 0,
+        };
+
+        let mut timestamp = first + self.config.lookback_days as f64 * DAY_MS;
+        while timestamp <= last {
+            let Some(sol_price) = sol.price_at(timestamp) else {
+                timestamp += DAY_MS;
+                continue;
+            };
+
+            let mut prices_in_sol = HashMap::new();
+            let mut candidates = vec![];
+            for (fixture, token_info) in tokens.iter().zip(token_infos.iter()) {
+                if let Some(price) = fixture.price_at(timestamp) {
+                    prices_in_sol.insert(token_info.address, price / sol_price);
+                }
+
+                let history = fixture.window(timestamp, self.config.lookback_days);
+                if history.prices.len() >= 2 {
+                    candidates.push(CandidateData {
+                        token: token_info.clone(),
+                        history,
+                    });
+                }
+            }
+
+            let trades = self.strategy.select(&candidates)?;
+            let trades = self.config.sizing.size(trades, &candidates);
+
+            // Sell tokens that are gaining profit and not in the trades
+            let mints = portfolio.holdings.keys().copied().collect::<Vec<_>>();
+            for mint in mints {
+                let in_trades = trades.iter().any(|t| t.token.address == mint);
+                let Some(&price) = prices_in_sol.get(&mint) else {
+                    continue;
+                };
+
+                let holding = portfolio.holdings.get_mut(&mint).unwrap();
+                if in_trades || holding.profit_margin(price) <= self.config.min_profit_rate {
+                    continue;
+                }
+
+                let amount = holding.holding_amount();
+                let sol_amount = fill_model.sell(amount, price, holding.token_info.decimals);
+                holding.update_sell(sol_amount, amount, self.config.cost_basis);
+
+                portfolio.sol_balance += sol_amount;
+                report.fees_paid += fill_model.fee_lamports;
+                report.traded_volume += sol_amount;
+                report.trades += 1;
+            }
+
+            // Buy tokens
+            let amount_to_buy = daily_budget.min(portfolio.sol_balance as f64);
+            for trade in trades {
+                let Some(&price) = prices_in_sol.get(&trade.token.address) else {
+                    continue;
+                };
+                let sol_amount = (amount_to_buy * trade.weight).floor() as u64;
+                if sol_amount == 0 || sol_amount > portfolio.sol_balance {
+                    continue;
+                }
+
+                let token_amount = fill_model.buy(sol_amount, price, trade.token.decimals);
+                portfolio
+                    .holdings
+                    .entry(trade.token.address)
+                    .or_insert_with(|| OtherTokenHolding::init(trade.token.clone()))
+                    .update_buy(sol_amount, token_amount);
+
+                portfolio.sol_balance -= sol_amount;
+                report.fees_paid += fill_model.fee_lamports;
+                report.traded_volume += sol_amount;
+                report.trades += 1;
+            }
+
+            report.equity_curve.push(EquityPoint {
+                timestamp: timestamp as u64,
+                nav: portfolio.nav(&prices_in_sol),
+            });
+
+            timestamp += DAY_MS;
+        }
+
+        report.realized_pnl = portfolio.holdings.values().map(|h| h.total_pnl).sum();
+
+        Ok(report)
+    }
+}
+
+/// Runs the configured strategy over the fixtures of a directory.
+pub fn run_fixtures(dir: impl AsRef<Path>) -> Result<BacktestReport> {
+    let config = Config::get();
+    let (sol, tokens) = load_fixtures(dir)?;
+
+    let strategy = config.strategy.build(config.strategy_max_picks);
+    let backtest = Backtest::new(BacktestConfig::from_config(config), strategy.as_ref());
+    let report = backtest.run(&sol, &tokens)?;
+
+    for point in &report.equity_curve {
+        tracing::info!(
+            "{}: NAV {:.4} SOL",
+            point.timestamp,
+            point.nav / LAMPORTS_PER_SOL as f64
+        );
+    }
+    tracing::info!("{}", report);
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{EqualWeightStrategy, MomentumStrategy, SizingMode};
+
+    fn config() -> BacktestConfig {
+        BacktestConfig {
+            initial_sol: 100.0,
+            daily_budget_sol: 10.0,
+            lookback_days: 3,
+            min_profit_rate: 0.1,
+            sizing: Sizing {
+                mode: SizingMode::Fixed,
+                kelly_max_fraction: 1.0,
+            },
+            cost_basis: CostBasis::Fifo,
+            fill_model: FillModel::new(5_000, 50),
+        }
+    }
+
+    fn fixture(symbol: &str, address: &str, prices: &[f64]) -> PriceFixture {
+        PriceFixture {
+            symbol: symbol.to_string(),
+            name: symbol.to_string(),
+            address: address.to_string(),
+            decimals: 6,
+            coingecko_id: None,
+            history: HistoricalPriceResponse {
+                prices: prices
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| vec![i as f64 * DAY_MS, *p])
+                    .collect(),
+                market_caps: vec![],
+                total_volumes: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_backtest_fixtures() {
+        let (sol, tokens) = load_fixtures("assets/backtest").unwrap();
+        assert_eq!(tokens.len(), 3);
+
+        let strategy = MomentumStrategy::new();
+        let report = Backtest::new(config(), &strategy)
+            .run(&sol, &tokens)
+            .unwrap();
+
+        // 30 days of data, the first 3 are the lookback
+        assert_eq!(report.equity_curve.len(), 27);
+        assert!(report.trades > 0);
+        assert!(report.fees_paid > 0);
+        assert!(report.max_drawdown() >= 0.0 && report.max_drawdown() < 1.0);
+    }
+
+    #[test]
+    fn test_backtest_rising_token() {
+        let sol = fixture("SOL", SOL_MINT, &[100.0; 6]);
+        let token = fixture(
+            "UP",
+            "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263",
+            &[1.0, 1.0, 1.0, 1.0, 2.0, 4.0],
+        );
+
+        let strategy = EqualWeightStrategy::new(1);
+        let mut config = config();
+        config.fill_model = FillModel::new(0, 0);
+        let report = Backtest::new(config, &strategy)
+            .run(&sol, &[token])
+            .unwrap();
+
+        // Bought 10 SOL on each of the 3 days at 0.01, 0.02 and 0.04 SOL
+        assert_eq!(report.trades, 3);
+        assert_eq!(report.traded_volume, 30 * LAMPORTS_PER_SOL);
+        let expected_nav = 70.0 + (1000.0 + 500.0 + 250.0) * 0.04;
+        assert!((report.final_nav() / LAMPORTS_PER_SOL as f64 - expected_nav).abs() < 1e-6);
+        assert_eq!(report.max_drawdown(), 0.0);
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+
+use std::sync::OnceLock;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::twitter::TwitterAction;
+use crate::actions::utils::get_cur_timestamp;
+use crate::actions::Action;
+use crate::config::Config;
+use crate::constant::*;
+use crate::paper::trading_prefix;
+use crate::portfolio::Portfolio;
+use crate::store::{LocalStore, Store, StoreMap};
+use crate::token::jimmy::JimmyToken;
+use crate::twitter::TwitterClient;
+
+/// Realized PnL in lamports of the sells logged in `(start, end]`.
+pub fn realized_pnl(start: u64, end: u64) -> f64 {
+    PortfolioAction::iter()
+        .filter(|(_, raw)| raw.timestamp() > start && raw.timestamp() <= end)
+        .filter_map(|(action, _)| match action {
+            PortfolioAction::Pnl { pnl, .. } => Some(pnl),
+            _ => None,
+        })
+        .sum()
+}
+
+/// Lamports spent on the buyback of a period with the given realized PnL.
+pub fn buyback_amount(realized_pnl: f64, share: f64) -> u64 {
+    (realized_pnl * share).max(0.0).floor() as u64
+}
+
+/// Shares the realized profit of every period with the JIMMY holders by
+/// buying JIMMY back, and optionally burning it. The JIMMY bought back and
+/// not burned is held, never sold to fund the trades.
+pub struct Buyback {
+    state: StoreMap<String, u64, LocalStore>,
+}
+
+impl Buyback {
+    const BUYBACK_STATE_PREFIX: &'static str = "buyback_state";
+    const PERIOD_START_KEY: &'static str = "period_start";
+    const HELD_JIMMY_KEY: &'static str = "held_jimmy";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<Buyback> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            state: LocalStore::open_map(trading_prefix(Self::BUYBACK_STATE_PREFIX)),
+        }
+    }
+
+    /// Raw JIMMY bought back and still held in the wallet.
+    pub fn held_jimmy(&self) -> Result<u64> {
+        Ok(self
+            .state
+            .get(&Self::HELD_JIMMY_KEY.to_string())?
+            .unwrap_or_default())
+    }
+
+    /// Runs the buyback of the current period if it is over.
+    pub async fn run_if_due(&self) -> Result<()> {
+        let config = Config::get();
+        let key = Self::PERIOD_START_KEY.to_string();
+        let now = get_cur_timestamp();
+
+        let Some(period_start) = self.state.get(&key)? else {
+            tracing::info!("Starting the first buyback period");
+            return self.state.insert(key, now);
+        };
+        if period_start + config.buyback_interval_days * 60 * 60 * 24 > now {
+            return Ok(());
+        }
+
+        let pnl = realized_pnl(period_start, now);
+        let sol_amount = buyback_amount(pnl, config.buyback_share);
+        tracing::info!(
+            "Buyback period ended with {} SOL(LAMPORT) of realized PnL, buying back with {} SOL(LAMPORT)",
+            pnl,
+            sol_amount
+        );
+
+        if sol_amount > 0 {
+            self.buyback(period_start, now, pnl, sol_amount).await
+        } else {
+            self.state.insert(key, now)
+        }
+    }
+
+    async fn buyback(
+        &self,
+        period_start: u64,
+        period_end: u64,
+        pnl: f64,
+        sol_amount: u64,
+    ) -> Result<()> {
+        let config = Config::get();
+        let portfolio = Portfolio::get().await;
+
+        let (jimmy_amount, sig) = portfolio.buy_jimmy(sol_amount).await?;
+        // Close the period as soon as the profit is spent, so a failure below
+        // never buys back twice
+        self.state
+            .insert(Self::PERIOD_START_KEY.to_string(), period_end)?;
+
+        let burn_sig = if config.buyback_burn {
+            JimmyToken::get()
+                .await
+                .burn(jimmy_amount)
+                .inspect_err(|e| tracing::error!("Failed to burn JIMMY: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        if burn_sig.is_none() {
+            let held_jimmy = self.held_jimmy()? + jimmy_amount;
+            self.state
+                .insert(Self::HELD_JIMMY_KEY.to_string(), held_jimmy)?;
+        }
+
+        PortfolioAction::buyback(
+            period_start,
+            period_end,
+            pnl,
+            sol_amount,
+            jimmy_amount,
+            sig.to_string(),
+            burn_sig.map(|sig| sig.to_string()),
+        )
+        .log();
+
+        let mut tweet_text = format!(
+            "Buyback: {:.4} SOL, {:.0}% of {:.4} SOL realized profit, bought {:.2} $JIMMY https://solscan.io/tx/{}",
+            sol_amount as f64 / LAMPORTS_PER_SOL as f64,
+            config.buyback_share * 100.0,
+            pnl / LAMPORTS_PER_SOL as f64,
+            jimmy_amount as f64 / JimmyToken::one_jimmy() as f64,
+            sig
+        );
+        if let Some(burn_sig) = burn_sig {
+            tweet_text.push_str(&format!(
+                " and burned them https://solscan.io/tx/{}",
+                burn_sig
+            ));
+        }
+
+        let tweet_id = TwitterClient::get().post_tweet(&tweet_text).await?;
+        TwitterAction::Buyback {
+            tweet_id,
+            tweet_text,
+        }
+        .log();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buyback_amount() {
+        assert_eq!(
+            buyback_amount(5.0 * LAMPORTS_PER_SOL as f64, 0.1),
+            500_000_000
+        );
+        assert_eq!(buyback_amount(-1.0 * LAMPORTS_PER_SOL as f64, 0.1), 0);
+    }
+}
@@ -3,6 +3,10 @@ use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
 
 use crate::constant::*;
+use crate::cost_basis::CostBasis;
+use crate::strategy::{SizingMode, StrategyKind};
+use crate::swap::SwapBackend;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     // Network configuration
@@ -33,6 +37,7 @@ pub struct Config {
     // Price API configuration
     pub coingecko_api_key: Option<String>,
     pub coinmarketcap_api_key: Option<String>,
+    pub price_max_dispersion: f64,
 
     // Jupiter configuration
     pub jupiter_rpc_url: String,
@@ -44,11 +49,70 @@ pub struct Config {
     pub twitter_access_token: String,
     pub twitter_access_token_secret: String,
 
-    // Mock trade configuration
-    pub mock_trade: bool,
+    // Trade configuration
+    pub paper_trading: bool,
+    pub paper_initial_sol: f64,
+    pub paper_initial_jimmy: u64,
+    pub paper_fee_lamports: u64,
+    pub paper_slippage_bps: u64,
     pub sell_jimmy_amount: f64,
     pub max_sol_trading_amount_one_day: f64,
     pub min_profit_rate: f64,
+    pub cost_basis: CostBasis,
+
+    // Strategy configuration
+    pub strategy: StrategyKind,
+    pub strategy_lookback_days: u32,
+    pub strategy_max_picks: usize,
+    pub sizing: SizingMode,
+    pub kelly_max_fraction: f64,
+    pub min_volume_usd: f64,
+    pub min_market_cap_usd: f64,
+    pub max_price_impact: f64,
+
+    // Risk configuration
+    pub risk_max_position_share: f64,
+    pub risk_max_non_sol_share: f64,
+    pub risk_max_open_positions: usize,
+    pub risk_max_drawdown: f64,
+    pub rebalance_drift_tolerance: f64,
+
+    // Exit monitor configuration
+    pub exit_monitor_interval_secs: u64,
+    pub stop_loss_rate: f64,
+    pub trailing_stop_rate: f64,
+    pub take_profit_rate: f64,
+    pub order_monitor_interval_secs: u64,
+    pub twap_slices: usize,
+    pub twap_window_secs: u64,
+    pub twap_min_slice_sol: f64,
+
+    // Buyback configuration
+    pub buyback_share: f64,
+    pub buyback_interval_days: u64,
+    pub buyback_burn: bool,
+
+    // Swap configuration
+    pub swap_backend: SwapBackend,
+    pub quote_max_price_impact: f64,
+    pub quote_max_price_deviation: f64,
+    pub priority_fee_percentile: f64,
+    pub priority_fee_max_micro_lamports: u64,
+    pub slippage_min_bps: u64,
+    pub slippage_max_bps: u64,
+    pub swap_resend_interval_ms: u64,
+    pub swap_max_requotes: usize,
+
+    // Reconciliation configuration
+    pub reconcile_fix: bool,
+
+    // NAV history configuration
+    pub nav_snapshot_interval_secs: u64,
+
+    // Backtest configuration
+    pub backtest_initial_sol: f64,
+    pub backtest_fee_lamports: u64,
+    pub backtest_slippage_bps: u64,
 
     // Substack configuration
     pub substack_urls: Vec<String>,
@@ -113,6 +177,10 @@ impl Config {
 
             let coingecko_api_key = std::env::var("COINGECKO_API_KEY").ok();
             let coinmarketcap_api_key = std::env::var("COINMARKETCAP_API_KEY").ok();
+            let price_max_dispersion = std::env::var("PRICE_MAX_DISPERSION")
+                .unwrap_or_else(|_| "0.05".into())
+                .parse()
+                .expect("PRICE_MAX_DISPERSION must be a valid f64");
 
             let jupiter_rpc_url =
                 std::env::var("JUPITER_RPC_URL").expect("JUPITER_RPC_URL is not set");
@@ -128,10 +196,38 @@ impl Config {
             let twitter_access_token_secret =
                 std::env::var("TWITTER_ACCESS_TOKEN_SECRET").unwrap_or_default();
 
-            let mock_trade = std::env::var("MOCK_TRADE")
-                .unwrap_or_else(|_| "false".into())
+            // MOCK_TRADE is the deprecated name of PAPER_TRADING
+            let paper_trading = match (
+                std::env::var("PAPER_TRADING").ok(),
+                std::env::var("MOCK_TRADE").ok(),
+            ) {
+                (Some(paper_trading), _) => paper_trading
+                    .parse()
+                    .expect("PAPER_TRADING must be a valid boolean"),
+                (None, Some(mock_trade)) => {
+                    tracing::warn!("MOCK_TRADE is deprecated, set PAPER_TRADING instead");
+                    mock_trade
+                        .parse()
+                        .expect("MOCK_TRADE must be a valid boolean")
+                }
+                (None, None) => false,
+            };
+            let paper_initial_sol = std::env::var("PAPER_INITIAL_SOL")
+                .unwrap_or_else(|_| "100.0".into())
+                .parse()
+                .expect("PAPER_INITIAL_SOL must be a valid f64");
+            let paper_initial_jimmy = std::env::var("PAPER_INITIAL_JIMMY")
+                .unwrap_or_else(|_| "1000000".into())
                 .parse()
-                .expect("MOCK_TRADE must be a valid boolean");
+                .expect("PAPER_INITIAL_JIMMY must be a valid u64");
+            let paper_fee_lamports = std::env::var("PAPER_FEE_LAMPORTS")
+                .unwrap_or_else(|_| "205000".into())
+                .parse()
+                .expect("PAPER_FEE_LAMPORTS must be a valid u64");
+            let paper_slippage_bps = std::env::var("PAPER_SLIPPAGE_BPS")
+                .unwrap_or_else(|_| "50".into())
+                .parse()
+                .expect("PAPER_SLIPPAGE_BPS must be a valid u64");
             let sell_jimmy_amount = std::env::var("SELL_JIMMY_AMOUNT")
                 .expect("SELL_JIMMY_AMOUNT is not set")
                 .parse()
@@ -144,6 +240,178 @@ impl Config {
                 .expect("MIN_PROFIT_RATE is not set")
                 .parse()
                 .expect("MIN_PROFIT_RATE must be a valid f64");
+            let cost_basis = std::env::var("COST_BASIS")
+                .unwrap_or_else(|_| "lifo".into())
+                .parse()
+                .expect("COST_BASIS must be one of fifo, lifo, hifo, average");
+
+            let strategy = std::env::var("STRATEGY")
+                .unwrap_or_else(|_| "momentum".into())
+                .parse()
+                .expect("STRATEGY must be one of momentum, equal_weight, mean_reversion, inverse_volatility, trend");
+            let strategy_lookback_days = std::env::var("STRATEGY_LOOKBACK_DAYS")
+                .unwrap_or_else(|_| "3".into())
+                .parse()
+                .expect("STRATEGY_LOOKBACK_DAYS must be a valid u32");
+            let strategy_max_picks = std::env::var("STRATEGY_MAX_PICKS")
+                .unwrap_or_else(|_| "3".into())
+                .parse()
+                .expect("STRATEGY_MAX_PICKS must be a valid usize");
+            let sizing = std::env::var("SIZING")
+                .unwrap_or_else(|_| "fixed".into())
+                .parse()
+                .expect("SIZING must be a valid sizing mode");
+            let kelly_max_fraction = std::env::var("KELLY_MAX_FRACTION")
+                .unwrap_or_else(|_| "1.0".into())
+                .parse()
+                .expect("KELLY_MAX_FRACTION must be a valid f64");
+            let min_volume_usd = std::env::var("MIN_VOLUME_USD")
+                .unwrap_or_else(|_| "100000".into())
+                .parse()
+                .expect("MIN_VOLUME_USD must be a valid f64");
+            let min_market_cap_usd = std::env::var("MIN_MARKET_CAP_USD")
+                .unwrap_or_else(|_| "1000000".into())
+                .parse()
+                .expect("MIN_MARKET_CAP_USD must be a valid f64");
+            let max_price_impact = std::env::var("MAX_PRICE_IMPACT")
+                .unwrap_or_else(|_| "0.02".into())
+                .parse()
+                .expect("MAX_PRICE_IMPACT must be a valid f64");
+
+            let risk_max_position_share = std::env::var("RISK_MAX_POSITION_SHARE")
+                .unwrap_or_else(|_| "0.2".into())
+                .parse()
+                .expect("RISK_MAX_POSITION_SHARE must be a valid f64");
+            let risk_max_non_sol_share = std::env::var("RISK_MAX_NON_SOL_SHARE")
+                .unwrap_or_else(|_| "0.8".into())
+                .parse()
+                .expect("RISK_MAX_NON_SOL_SHARE must be a valid f64");
+            let risk_max_open_positions = std::env::var("RISK_MAX_OPEN_POSITIONS")
+                .unwrap_or_else(|_| "10".into())
+                .parse()
+                .expect("RISK_MAX_OPEN_POSITIONS must be a valid usize");
+            let risk_max_drawdown = std::env::var("RISK_MAX_DRAWDOWN")
+                .unwrap_or_else(|_| "0.3".into())
+                .parse()
+                .expect("RISK_MAX_DRAWDOWN must be a valid f64");
+            let rebalance_drift_tolerance = std::env::var("REBALANCE_DRIFT_TOLERANCE")
+                .unwrap_or_else(|_| "0.1".into())
+                .parse()
+                .expect("REBALANCE_DRIFT_TOLERANCE must be a valid f64");
+
+            let exit_monitor_interval_secs = std::env::var("EXIT_MONITOR_INTERVAL_SECS")
+                .unwrap_or_else(|_| "300".into())
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .expect("EXIT_MONITOR_INTERVAL_SECS must be a u64 greater than 0");
+            let stop_loss_rate = std::env::var("STOP_LOSS_RATE")
+                .unwrap_or_else(|_| "0.2".into())
+                .parse()
+                .expect("STOP_LOSS_RATE must be a valid f64");
+            let trailing_stop_rate = std::env::var("TRAILING_STOP_RATE")
+                .unwrap_or_else(|_| "0.25".into())
+                .parse()
+                .expect("TRAILING_STOP_RATE must be a valid f64");
+            let take_profit_rate = std::env::var("TAKE_PROFIT_RATE")
+                .unwrap_or_else(|_| "1.0".into())
+                .parse()
+                .expect("TAKE_PROFIT_RATE must be a valid f64");
+
+            let order_monitor_interval_secs = std::env::var("ORDER_MONITOR_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".into())
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .expect("ORDER_MONITOR_INTERVAL_SECS must be a u64 greater than 0");
+
+            let twap_slices = std::env::var("TWAP_SLICES")
+                .unwrap_or_else(|_| "4".into())
+                .parse()
+                .expect("TWAP_SLICES must be a valid usize");
+            let twap_window_secs = std::env::var("TWAP_WINDOW_SECS")
+                .unwrap_or_else(|_| "3600".into())
+                .parse()
+                .expect("TWAP_WINDOW_SECS must be a valid u64");
+            let twap_min_slice_sol = std::env::var("TWAP_MIN_SLICE_SOL")
+                .unwrap_or_else(|_| "0.05".into())
+                .parse()
+                .expect("TWAP_MIN_SLICE_SOL must be a valid f64");
+
+            let buyback_share = std::env::var("BUYBACK_SHARE")
+                .unwrap_or_else(|_| "0.1".into())
+                .parse()
+                .expect("BUYBACK_SHARE must be a valid f64");
+            let buyback_interval_days = std::env::var("BUYBACK_INTERVAL_DAYS")
+                .unwrap_or_else(|_| "30".into())
+                .parse()
+                .expect("BUYBACK_INTERVAL_DAYS must be a valid u64");
+            let buyback_burn = std::env::var("BUYBACK_BURN")
+                .unwrap_or_else(|_| "false".into())
+                .parse()
+                .expect("BUYBACK_BURN must be a valid boolean");
+
+            let swap_backend = std::env::var("SWAP_BACKEND")
+                .unwrap_or_else(|_| "jupiter".into())
+                .parse()
+                .expect("SWAP_BACKEND must be jupiter or raydium");
+            let quote_max_price_impact = std::env::var("QUOTE_MAX_PRICE_IMPACT")
+                .unwrap_or_else(|_| "0.05".into())
+                .parse()
+                .expect("QUOTE_MAX_PRICE_IMPACT must be a valid f64");
+            let quote_max_price_deviation = std::env::var("QUOTE_MAX_PRICE_DEVIATION")
+                .unwrap_or_else(|_| "0.05".into())
+                .parse()
+                .expect("QUOTE_MAX_PRICE_DEVIATION must be a valid f64");
+            let priority_fee_percentile = std::env::var("PRIORITY_FEE_PERCENTILE")
+                .unwrap_or_else(|_| "75".into())
+                .parse()
+                .expect("PRIORITY_FEE_PERCENTILE must be a valid f64");
+            let priority_fee_max_micro_lamports = std::env::var("PRIORITY_FEE_MAX_MICRO_LAMPORTS")
+                .unwrap_or_else(|_| "1000000".into())
+                .parse()
+                .expect("PRIORITY_FEE_MAX_MICRO_LAMPORTS must be a valid u64");
+            let slippage_min_bps = std::env::var("SLIPPAGE_MIN_BPS")
+                .unwrap_or_else(|_| "50".into())
+                .parse()
+                .expect("SLIPPAGE_MIN_BPS must be a valid u64");
+            let slippage_max_bps = std::env::var("SLIPPAGE_MAX_BPS")
+                .unwrap_or_else(|_| "500".into())
+                .parse()
+                .expect("SLIPPAGE_MAX_BPS must be a valid u64");
+            let swap_resend_interval_ms = std::env::var("SWAP_RESEND_INTERVAL_MS")
+                .unwrap_or_else(|_| "2000".into())
+                .parse()
+                .expect("SWAP_RESEND_INTERVAL_MS must be a valid u64");
+            let swap_max_requotes = std::env::var("SWAP_MAX_REQUOTES")
+                .unwrap_or_else(|_| "2".into())
+                .parse()
+                .expect("SWAP_MAX_REQUOTES must be a valid usize");
+
+            let reconcile_fix = std::env::var("RECONCILE_FIX")
+                .unwrap_or_else(|_| "false".into())
+                .parse()
+                .expect("RECONCILE_FIX must be a valid boolean");
+
+            let nav_snapshot_interval_secs = std::env::var("NAV_SNAPSHOT_INTERVAL_SECS")
+                .unwrap_or_else(|_| "3600".into())
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .expect("NAV_SNAPSHOT_INTERVAL_SECS must be a u64 greater than 0");
+
+            let backtest_initial_sol = std::env::var("BACKTEST_INITIAL_SOL")
+                .unwrap_or_else(|_| "100.0".into())
+                .parse()
+                .expect("BACKTEST_INITIAL_SOL must be a valid f64");
+            let backtest_fee_lamports = std::env::var("BACKTEST_FEE_LAMPORTS")
+                .unwrap_or_else(|_| "205000".into())
+                .parse()
+                .expect("BACKTEST_FEE_LAMPORTS must be a valid u64");
+            let backtest_slippage_bps = std::env::var("BACKTEST_SLIPPAGE_BPS")
+                .unwrap_or_else(|_| "50".into())
+                .parse()
+                .expect("BACKTEST_SLIPPAGE_BPS must be a valid u64");
 
             let substack_urls = std::env::var("SUBSTACK_SUBSCRIPTION_URLS")
                 .map(|urls| urls.split(",").map(|s| s.to_string()).collect())
@@ -174,16 +442,60 @@ impl Config {
                 azure_openai_chat_model,
                 coingecko_api_key,
                 coinmarketcap_api_key,
+                price_max_dispersion,
                 jupiter_rpc_url,
                 use_twitter,
                 twitter_consumer_key,
                 twitter_consumer_key_secret,
                 twitter_access_token,
                 twitter_access_token_secret,
-                mock_trade,
+                paper_trading,
+                paper_initial_sol,
+                paper_initial_jimmy,
+                paper_fee_lamports,
+                paper_slippage_bps,
                 sell_jimmy_amount,
                 max_sol_trading_amount_one_day,
                 min_profit_rate,
+                cost_basis,
+                strategy,
+                strategy_lookback_days,
+                strategy_max_picks,
+                sizing,
+                kelly_max_fraction,
+                min_volume_usd,
+                min_market_cap_usd,
+                max_price_impact,
+                risk_max_position_share,
+                risk_max_non_sol_share,
+                risk_max_open_positions,
+                risk_max_drawdown,
+                rebalance_drift_tolerance,
+                exit_monitor_interval_secs,
+                stop_loss_rate,
+                trailing_stop_rate,
+                take_profit_rate,
+                order_monitor_interval_secs,
+                twap_slices,
+                twap_window_secs,
+                twap_min_slice_sol,
+                buyback_share,
+                buyback_interval_days,
+                buyback_burn,
+                swap_backend,
+                quote_max_price_impact,
+                quote_max_price_deviation,
+                priority_fee_percentile,
+                priority_fee_max_micro_lamports,
+                slippage_min_bps,
+                slippage_max_bps,
+                swap_resend_interval_ms,
+                swap_max_requotes,
+                reconcile_fix,
+                nav_snapshot_interval_secs,
+                backtest_initial_sol,
+                backtest_fee_lamports,
+                backtest_slippage_bps,
                 substack_urls,
                 store_path,
                 run_in_sgx,
@@ -1,10 +1,14 @@
 pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
 pub const SOL_COINGECKO_ID: &str = "solana";
+pub const SOL_COINMARKETCAP_ID: u64 = 5426;
 
 pub const USD_CURRENCY: &str = "usd";
+pub const SOL_CURRENCY: &str = "sol";
 
 pub const COMPUTE_UNIT_PRICE: u64 = 100000;
 pub const INNER_MAX_RETRIES: usize = 100;
 pub const OUTER_MAX_RETRIES: u64 = 10;
 pub const SKIP_PREFLIGHT: bool = true;
+pub const FILL_MAX_RETRIES: u64 = 5;
+pub const FILL_RETRY_DELAY_SECS: u64 = 2;
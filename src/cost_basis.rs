@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use std::str::FromStr;
+
+use crate::portfolio::OneShot;
+
+/// Method picking the lots a sale is taken from, which sets its realized PnL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostBasis {
+    /// First in, first out
+    Fifo,
+    /// Last in, first out
+    Lifo,
+    /// Highest cost in, first out
+    Hifo,
+    /// Average cost of all the lots, taken oldest first
+    Average,
+}
+
+impl FromStr for CostBasis {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "fifo" => Ok(CostBasis::Fifo),
+            "lifo" => Ok(CostBasis::Lifo),
+            "hifo" => Ok(CostBasis::Hifo),
+            "average" | "average_cost" => Ok(CostBasis::Average),
+            _ => Err(anyhow::anyhow!("Unknown cost basis method: {}", s)),
+        }
+    }
+}
+
+/// A lot of tokens, kept in acquisition order.
+pub trait Lot: Clone {
+    fn shot(&self) -> &OneShot;
+    fn shot_mut(&mut self) -> &mut OneShot;
+}
+
+impl Lot for OneShot {
+    fn shot(&self) -> &OneShot {
+        self
+    }
+
+    fn shot_mut(&mut self) -> &mut OneShot {
+        self
+    }
+}
+
+impl CostBasis {
+    /// Indices of the lots in the order a sale consumes them.
+    fn order<L: Lot>(&self, lots: &[L]) -> Vec<usize> {
+        let mut order = (0..lots.len()).collect::<Vec<_>>();
+        match self {
+            CostBasis::Fifo | CostBasis::Average => {}
+            CostBasis::Lifo => order.reverse(),
+            // Stable, so the oldest of equally costly lots goes first
+            CostBasis::Hifo => order.sort_by(|a, b| {
+                lots[*b]
+                    .shot()
+                    .avg_cost()
+                    .total_cmp(&lots[*a].shot().avg_cost())
+            }),
+        }
+        order
+    }
+
+    /// Takes `amount` tokens out of the lots, returning every lot touched
+    /// along with the part taken from it. Emptied lots are removed.
+    pub fn take<L: Lot>(&self, lots: &mut Vec<L>, amount: u64) -> Vec<(L, OneShot)> {
+        if *self == CostBasis::Average {
+            let quantity: u64 = lots.iter().map(|lot| lot.shot().quantity).sum();
+            let cost: f64 = lots.iter().map(|lot| lot.shot().total_cost_in_sol).sum();
+            if quantity > 0 {
+                for lot in lots.iter_mut() {
+                    let shot = lot.shot_mut();
+                    shot.total_cost_in_sol = cost * shot.quantity as f64 / quantity as f64;
+                }
+            }
+        }
+
+        let mut remaining = amount;
+        let mut taken = vec![];
+        for i in self.order(lots) {
+            if remaining == 0 {
+                break;
+            }
+
+            let lot = lots[i].clone();
+            let shot = lots[i].shot_mut();
+            let cut = remaining.min(shot.quantity);
+            if cut == 0 {
+                continue;
+            }
+            let cost = shot.avg_cost() * cut as f64;
+            shot.quantity -= cut;
+            shot.total_cost_in_sol = (shot.total_cost_in_sol - cost).max(0.0);
+            remaining -= cut;
+
+            taken.push((
+                lot,
+                OneShot {
+                    quantity: cut,
+                    total_cost_in_sol: cost,
+                },
+            ));
+        }
+
+        lots.retain(|lot| lot.shot().quantity > 0);
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lots() -> Vec<OneShot> {
+        [(100, 100.0), (100, 300.0), (100, 200.0)]
+            .into_iter()
+            .map(|(quantity, total_cost_in_sol)| OneShot {
+                quantity,
+                total_cost_in_sol,
+            })
+            .collect()
+    }
+
+    fn cost_of(method: CostBasis, amount: u64) -> (f64, Vec<OneShot>) {
+        let mut lots = lots();
+        let cost = method
+            .take(&mut lots, amount)
+            .iter()
+            .map(|(_, taken)| taken.total_cost_in_sol)
+            .sum();
+        (cost, lots)
+    }
+
+    #[test]
+    fn test_methods() {
+        let (fifo, left) = cost_of(CostBasis::Fifo, 150);
+        assert!((fifo - 250.0).abs() < 1e-9);
+        assert_eq!(left.len(), 2);
+        assert_eq!(left[0].quantity, 50);
+
+        let (lifo, _) = cost_of(CostBasis::Lifo, 150);
+        assert!((lifo - 350.0).abs() < 1e-9);
+
+        let (hifo, left) = cost_of(CostBasis::Hifo, 150);
+        assert!((hifo - 400.0).abs() < 1e-9);
+        assert!((left.iter().map(|l| l.total_cost_in_sol).sum::<f64>() - 200.0).abs() < 1e-9);
+
+        let (average, left) = cost_of(CostBasis::Average, 150);
+        assert!((average - 300.0).abs() < 1e-9);
+        assert!(left.iter().all(|l| (l.avg_cost() - 2.0).abs() < 1e-9));
+    }
+}
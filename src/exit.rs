@@ -0,0 +1,290 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use std::sync::OnceLock;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::Action;
+use crate::config::Config;
+use crate::paper::trading_prefix;
+use crate::portfolio::{OtherTokenHolding, Portfolio};
+use crate::store::{LocalStore, Store, StoreMap};
+use crate::token::store::SolanaTokenStore;
+use crate::token::structs::TokenInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ExitReason {
+    StopLoss,
+    TrailingStop,
+    TakeProfit,
+}
+
+impl std::fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitReason::StopLoss => write!(f, "stop-loss"),
+            ExitReason::TrailingStop => write!(f, "trailing stop"),
+            ExitReason::TakeProfit => write!(f, "take-profit"),
+        }
+    }
+}
+
+/// Exit thresholds of a holding, as rates from 0 to 1.
+///
+/// A threshold of 0 disables the rule.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ExitRules {
+    /// Loss from the cost basis that triggers a sell
+    pub stop_loss_rate: f64,
+    /// Drop from the highest observed price that triggers a sell
+    pub trailing_stop_rate: f64,
+    /// Profit over the cost basis that triggers a sell
+    pub take_profit_rate: f64,
+}
+
+impl ExitRules {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            stop_loss_rate: config.stop_loss_rate,
+            trailing_stop_rate: config.trailing_stop_rate,
+            take_profit_rate: config.take_profit_rate,
+        }
+    }
+
+    /// Checks the holding against the current price and the highest price
+    /// seen since it was opened, both in SOL per UI unit of the token.
+    pub fn evaluate(
+        &self,
+        holding: &OtherTokenHolding,
+        peak_price_in_sol: f64,
+        price_in_sol: f64,
+    ) -> Option<ExitReason> {
+        let profit = holding.profit_margin(price_in_sol);
+
+        if self.stop_loss_rate > 0.0 && profit <= -self.stop_loss_rate {
+            return Some(ExitReason::StopLoss);
+        }
+
+        if self.take_profit_rate > 0.0 && profit >= self.take_profit_rate {
+            return Some(ExitReason::TakeProfit);
+        }
+
+        if self.trailing_stop_rate > 0.0
+            && price_in_sol <= peak_price_in_sol * (1.0 - self.trailing_stop_rate)
+        {
+            return Some(ExitReason::TrailingStop);
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExitState {
+    /// Rules of this holding, the configured defaults apply if unset
+    pub rules: Option<ExitRules>,
+    /// Highest price in SOL observed while the holding is open
+    pub peak_price_in_sol: f64,
+}
+
+/// Watches the open holdings on a fast cadence and sells those hitting their
+/// stop-loss, trailing stop or take-profit threshold.
+pub struct ExitMonitor {
+    default_rules: ExitRules,
+    /// States by mint
+    states: StoreMap<Pubkey, ExitState, LocalStore>,
+}
+
+impl ExitMonitor {
+    const EXIT_STATES_PREFIX: &'static str = "exit_states";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<ExitMonitor> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            default_rules: ExitRules::from_config(Config::get()),
+            states: LocalStore::open_map(trading_prefix(Self::EXIT_STATES_PREFIX)),
+        }
+    }
+
+    /// Overrides the default exit rules of a holding, restored with `None`.
+    pub fn set_rules(&self, token_info: &TokenInfo, rules: Option<ExitRules>) -> Result<()> {
+        let mut state = self.states.get(&token_info.address)?.unwrap_or_default();
+        state.rules = rules;
+        self.states.insert(token_info.address, state)
+    }
+
+    pub async fn check(&self) -> Result<()> {
+        let portfolio = Portfolio::get().await;
+
+        // Forget the states of closed holdings
+        for (key, holding) in portfolio.tokens().iter() {
+            if holding.holding_amount() == 0 && self.states.get(&key)?.is_some() {
+                self.states.remove(&key)?;
+            }
+        }
+
+        let valuation = portfolio.valuation().await?;
+        for holding_valuation in valuation.holdings {
+            let price_in_sol = holding_valuation.price_in_sol;
+            let holding = holding_valuation.holding;
+            if price_in_sol <= 0.0 {
+                continue;
+            }
+
+            let key = holding.token_info.address;
+            let mut state = self.states.get(&key)?.unwrap_or_default();
+            state.peak_price_in_sol = state.peak_price_in_sol.max(price_in_sol);
+            self.states.insert(key, state.clone())?;
+
+            let rules = state.rules.unwrap_or(self.default_rules);
+            let Some(reason) = rules.evaluate(&holding, state.peak_price_in_sol, price_in_sol)
+            else {
+                continue;
+            };
+
+            let profit = holding.profit_margin(price_in_sol);
+            tracing::info!(
+                "{} triggered for {}: price {} SOL, peak {} SOL, profit {:.2}%",
+                reason,
+                holding.token_info.symbol,
+                price_in_sol,
+                state.peak_price_in_sol,
+                profit * 100.0
+            );
+
+            PortfolioAction::exit(
+                holding.token_info.address.to_string(),
+                reason,
+                price_in_sol,
+                profit,
+            )
+            .log();
+
+            let amount = holding.holding_amount();
+            if let Err(e) = portfolio.sell_token(&holding.token_info, amount).await {
+                tracing::error!("Failed to sell {}: {}", holding.token_info.symbol, e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Lists or sets the exit rules of the holdings, `jimmy exits [<token>
+/// <stop loss> <trailing stop> <take profit> | reset <token>]`.
+///
+/// Rates are from 0 to 1, a rate of 0 disabling the rule.
+pub async fn manage(args: &[String]) -> Result<()> {
+    let monitor = ExitMonitor::get();
+    let token_info = |token: &str| {
+        let token = token.to_string();
+        async move {
+            SolanaTokenStore::get()
+                .get_token_info(&token)
+                .await?
+                .ok_or(anyhow::anyhow!("Token {} not found", token))
+        }
+    };
+
+    match args {
+        [] => {
+            tracing::info!("Default exit rules: {:?}", monitor.default_rules);
+            for (mint, state) in monitor.states.iter() {
+                if let Some(rules) = state.rules {
+                    tracing::info!("Exit rules of {}: {:?}", mint, rules);
+                }
+            }
+        }
+        [reset, token] if reset == "reset" => {
+            let token_info = token_info(token).await?;
+            monitor.set_rules(&token_info, None)?;
+            tracing::info!("Default exit rules restored for {}", token_info.symbol);
+        }
+        [token, stop_loss_rate, trailing_stop_rate, take_profit_rate] => {
+            let token_info = token_info(token).await?;
+            let rules = ExitRules {
+                stop_loss_rate: stop_loss_rate.parse()?,
+                trailing_stop_rate: trailing_stop_rate.parse()?,
+                take_profit_rate: take_profit_rate.parse()?,
+            };
+            let rates = [rules.stop_loss_rate, rules.trailing_stop_rate];
+            if rates.iter().any(|rate| !(0.0..=1.0).contains(rate)) || rules.take_profit_rate < 0.0
+            {
+                return Err(anyhow::anyhow!("Invalid exit rules: {:?}", rules));
+            }
+            monitor.set_rules(&token_info, Some(rules))?;
+            tracing::info!("Exit rules of {} set: {:?}", token_info.symbol, rules);
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Usage: jimmy exits [<token> <stop loss> <trailing stop> <take profit> | reset <token>]"
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::LAMPORTS_PER_SOL;
+
+    fn rules() -> ExitRules {
+        ExitRules {
+            stop_loss_rate: 0.2,
+            trailing_stop_rate: 0.25,
+            take_profit_rate: 1.0,
+        }
+    }
+
+    /// 1 token bought for 1 SOL
+    fn holding() -> OtherTokenHolding {
+        let mut holding = OtherTokenHolding::init(TokenInfo {
+            address: Pubkey::new_unique(),
+            decimals: 6,
+            name: "Test".to_string(),
+            symbol: "TEST".to_string(),
+            coingecko_id: None,
+        });
+        holding.update_buy(LAMPORTS_PER_SOL, 1_000_000);
+        holding
+    }
+
+    #[test]
+    fn test_exit_rules() {
+        let holding = holding();
+        let rules = rules();
+
+        assert_eq!(rules.evaluate(&holding, 1.0, 1.0), None);
+        assert_eq!(
+            rules.evaluate(&holding, 1.0, 0.75),
+            Some(ExitReason::StopLoss)
+        );
+        assert_eq!(
+            rules.evaluate(&holding, 2.5, 2.1),
+            Some(ExitReason::TakeProfit)
+        );
+        assert_eq!(
+            rules.evaluate(&holding, 1.8, 1.3),
+            Some(ExitReason::TrailingStop)
+        );
+        assert_eq!(rules.evaluate(&holding, 1.8, 1.4), None);
+    }
+
+    #[test]
+    fn test_disabled_rules() {
+        let rules = ExitRules {
+            stop_loss_rate: 0.0,
+            trailing_stop_rate: 0.0,
+            take_profit_rate: 0.0,
+        };
+
+        assert_eq!(rules.evaluate(&holding(), 10.0, 0.1), None);
+    }
+}
@@ -0,0 +1,230 @@
+//! Fills of the confirmed swaps, read from the balances in the transaction
+//! meta rather than trusted from the quote.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+
+use crate::client::get_confirmed_client;
+use crate::constant::*;
+use crate::journal::IntentKind;
+use crate::wallet::Wallet;
+
+/// Changes a transaction made to the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceChanges {
+    /// Base and priority fees
+    pub fee_lamports: u64,
+    /// Lamports and wrapped SOL, fees and rents included
+    pub sol_change: i128,
+    /// Raw amount of the traded token
+    pub token_change: i128,
+}
+
+impl BalanceChanges {
+    /// Reads the changes of the fee payer `wallet` from the meta.
+    pub fn from_meta(meta: &UiTransactionStatusMeta, wallet: &Pubkey, mint: &Pubkey) -> Self {
+        let pre_tokens: Option<Vec<_>> = meta.pre_token_balances.clone().into();
+        let post_tokens: Option<Vec<_>> = meta.post_token_balances.clone().into();
+        let (pre_tokens, post_tokens) = (
+            pre_tokens.unwrap_or_default(),
+            post_tokens.unwrap_or_default(),
+        );
+        let token_change = |mint: &Pubkey| {
+            token_balance(&post_tokens, wallet, mint) - token_balance(&pre_tokens, wallet, mint)
+        };
+
+        // The fee payer comes first, and its wrapped SOL may be unwrapped
+        let lamport_change = match (meta.pre_balances.first(), meta.post_balances.first()) {
+            (Some(pre), Some(post)) => *post as i128 - *pre as i128,
+            _ => 0,
+        };
+
+        Self {
+            fee_lamports: meta.fee,
+            sol_change: lamport_change + token_change(&spl_token::native_mint::id()),
+            token_change: token_change(mint),
+        }
+    }
+}
+
+fn token_balance(balances: &[UiTransactionTokenBalance], owner: &Pubkey, mint: &Pubkey) -> i128 {
+    let (owner, mint) = (owner.to_string(), mint.to_string());
+    balances
+        .iter()
+        .filter(|balance| {
+            let balance_owner: Option<String> = balance.owner.clone().into();
+            balance.mint == mint && balance_owner.as_ref() == Some(&owner)
+        })
+        .filter_map(|balance| balance.ui_token_amount.amount.parse::<i128>().ok())
+        .sum()
+}
+
+/// What a swap really cost and returned.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SwapFill {
+    /// Base and priority fees of the transaction
+    pub fee_lamports: u64,
+    /// Lamports spent by a buy or received by a sell, net of every cost
+    pub net_sol: u64,
+    /// Raw tokens received by a buy or spent by a sell
+    pub token_amount: u64,
+    /// Output of the quote, raw tokens for a buy and lamports for a sell
+    pub quoted_out: u64,
+    /// Output received, before the fees for a sell
+    pub actual_out: u64,
+}
+
+impl SwapFill {
+    pub fn new(kind: IntentKind, changes: BalanceChanges, quoted_out: u64) -> Self {
+        let clamp = |amount: i128| amount.clamp(0, u64::MAX as i128) as u64;
+        let (net_sol, token_amount, actual_out) = match kind {
+            IntentKind::Buy => {
+                let token_amount = clamp(changes.token_change);
+                (clamp(-changes.sol_change), token_amount, token_amount)
+            }
+            IntentKind::Sell => {
+                let net_sol = clamp(changes.sol_change);
+                (
+                    net_sol,
+                    clamp(-changes.token_change),
+                    net_sol + changes.fee_lamports,
+                )
+            }
+        };
+
+        Self {
+            fee_lamports: changes.fee_lamports,
+            net_sol,
+            token_amount,
+            quoted_out,
+            actual_out,
+        }
+    }
+
+    /// Shortfall of the output against the quote, as a rate.
+    pub fn slippage(&self) -> f64 {
+        if self.quoted_out == 0 {
+            return 0.0;
+        }
+        (self.quoted_out as f64 - self.actual_out as f64) / self.quoted_out as f64
+    }
+
+    /// Price of one UI unit of the token in SOL, all costs included.
+    pub fn effective_price(&self, decimals: u8) -> f64 {
+        if self.token_amount == 0 {
+            return 0.0;
+        }
+        let ui_amount = self.token_amount as f64 / 10_f64.powi(decimals as i32);
+        self.net_sol as f64 / LAMPORTS_PER_SOL as f64 / ui_amount
+    }
+}
+
+/// Reads the fill of a confirmed swap of `mint` from its transaction.
+pub fn fetch_fill(
+    sig: &Signature,
+    mint: &Pubkey,
+    kind: IntentKind,
+    quoted_out: u64,
+) -> Result<SwapFill> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let tx = get_confirmed_client().get_transaction_with_config(sig, config)?;
+    let meta = tx
+        .transaction
+        .meta
+        .ok_or(anyhow::anyhow!("No meta in transaction {}", sig))?;
+
+    let changes = BalanceChanges::from_meta(&meta, &Wallet::get().pubkey(), mint);
+    Ok(SwapFill::new(kind, changes, quoted_out))
+}
+
+/// Slippage and fees over a set of fills.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SlippageStats {
+    pub swaps: usize,
+    pub mean_slippage: f64,
+    pub max_slippage: f64,
+    pub fees_lamports: u64,
+}
+
+impl SlippageStats {
+    pub fn from_fills<'a>(fills: impl IntoIterator<Item = &'a SwapFill>) -> Self {
+        let mut stats = Self::default();
+        let mut total_slippage = 0.0;
+        for fill in fills {
+            let slippage = fill.slippage();
+            stats.max_slippage = if stats.swaps == 0 {
+                slippage
+            } else {
+                stats.max_slippage.max(slippage)
+            };
+            stats.swaps += 1;
+            stats.fees_lamports += fill.fee_lamports;
+            total_slippage += slippage;
+        }
+        if stats.swaps > 0 {
+            stats.mean_slippage = total_slippage / stats.swaps as f64;
+        }
+        stats
+    }
+}
+
+impl std::fmt::Display for SlippageStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} swaps, mean slippage {:.3}%, max slippage {:.3}%, {} lamports of fees",
+            self.swaps,
+            self.mean_slippage * 100.0,
+            self.max_slippage * 100.0,
+            self.fees_lamports
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fills() {
+        // Spent 1 SOL, 5000 lamports of fees and 2039280 of ATA rent
+        let buy = SwapFill::new(
+            IntentKind::Buy,
+            BalanceChanges {
+                fee_lamports: 5_000,
+                sol_change: -1_002_044_280,
+                token_change: 1_980_000,
+            },
+            2_000_000,
+        );
+        assert_eq!(buy.net_sol, 1_002_044_280);
+        assert!((buy.slippage() - 0.01).abs() < 1e-9);
+        assert!((buy.effective_price(6) - 0.506083).abs() < 1e-6);
+
+        let sell = SwapFill::new(
+            IntentKind::Sell,
+            BalanceChanges {
+                fee_lamports: 5_000,
+                sol_change: 994_995_000,
+                token_change: -1_980_000,
+            },
+            1_000_000_000,
+        );
+        assert_eq!((sell.net_sol, sell.token_amount), (994_995_000, 1_980_000));
+        assert!((sell.slippage() - 0.005).abs() < 1e-9);
+
+        let stats = SlippageStats::from_fills([&buy, &sell]);
+        assert_eq!(stats.swaps, 2);
+        assert!((stats.mean_slippage - 0.0075).abs() < 1e-9);
+        assert!((stats.max_slippage - 0.01).abs() < 1e-9);
+        assert_eq!(stats.fees_lamports, 10_000);
+    }
+}
@@ -0,0 +1,243 @@
+//! Technical indicators over price series.
+//!
+//! The indicators take the prices oldest first, as extracted by [`closes`]
+//! from the `[timestamp, price]` series of CoinGecko. Rolling indicators
+//! return one value per complete window, so their output is shorter than the
+//! input and empty when there are not enough prices.
+
+/// Prices of a `[timestamp, price]` series.
+pub fn closes(series: &[Vec<f64>]) -> Vec<f64> {
+    series.iter().map(|p| p[1]).collect()
+}
+
+/// Simple moving average.
+pub fn sma(prices: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || prices.len() < period {
+        return vec![];
+    }
+
+    prices
+        .windows(period)
+        .map(|w| w.iter().sum::<f64>() / period as f64)
+        .collect()
+}
+
+/// Exponential moving average, seeded with the simple moving average of the
+/// first `period` prices.
+pub fn ema(prices: &[f64], period: usize) -> Vec<f64> {
+    let Some(&seed) = sma(&prices[..period.min(prices.len())], period).first() else {
+        return vec![];
+    };
+
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut values = vec![seed];
+    for price in &prices[period..] {
+        let last = *values.last().unwrap();
+        values.push(last + alpha * (price - last));
+    }
+
+    values
+}
+
+/// Relative strength index with Wilder's smoothing, from 0 to 100.
+pub fn rsi(prices: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || prices.len() <= period {
+        return vec![];
+    }
+
+    let changes = prices.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+    let rsi = |gain: f64, loss: f64| {
+        if loss == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        }
+    };
+
+    let mut gain = changes[..period].iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+    let mut loss = changes[..period].iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+
+    let mut values = vec![rsi(gain, loss)];
+    for change in &changes[period..] {
+        gain = (gain * (period - 1) as f64 + change.max(0.0)) / period as f64;
+        loss = (loss * (period - 1) as f64 + (-change).max(0.0)) / period as f64;
+        values.push(rsi(gain, loss));
+    }
+
+    values
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Macd {
+    /// Fast EMA minus slow EMA
+    pub macd: f64,
+    /// EMA of the MACD line
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+/// Moving average convergence divergence, usually with periods 12, 26 and 9.
+pub fn macd(prices: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<Macd> {
+    let fast_ema = ema(prices, fast);
+    let slow_ema = ema(prices, slow);
+    if fast == 0 || fast > slow || slow_ema.is_empty() {
+        return vec![];
+    }
+
+    // Align the fast EMA on the later start of the slow one
+    let line = fast_ema[slow - fast..]
+        .iter()
+        .zip(slow_ema.iter())
+        .map(|(f, s)| f - s)
+        .collect::<Vec<_>>();
+    let signal_line = ema(&line, signal);
+
+    line[line.len() - signal_line.len()..]
+        .iter()
+        .zip(signal_line)
+        .map(|(&macd, signal)| Macd {
+            macd,
+            signal,
+            histogram: macd - signal,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BollingerBand {
+    pub lower: f64,
+    pub middle: f64,
+    pub upper: f64,
+}
+
+/// Bollinger bands at `k` population standard deviations around the simple
+/// moving average, usually with a period of 20 and a `k` of 2.
+pub fn bollinger(prices: &[f64], period: usize, k: f64) -> Vec<BollingerBand> {
+    if period == 0 || prices.len() < period {
+        return vec![];
+    }
+
+    prices
+        .windows(period)
+        .map(|w| {
+            let middle = w.iter().sum::<f64>() / period as f64;
+            let std_dev =
+                (w.iter().map(|p| (p - middle).powi(2)).sum::<f64>() / period as f64).sqrt();
+            BollingerBand {
+                lower: middle - k * std_dev,
+                middle,
+                upper: middle + k * std_dev,
+            }
+        })
+        .collect()
+}
+
+/// Sample standard deviation of the log returns, per price interval.
+pub fn realized_volatility(prices: &[f64]) -> f64 {
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .filter(|w| w[0] > 0.0 && w[1] > 0.0)
+        .map(|w| (w[1] / w[0]).ln())
+        .collect();
+    if returns.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    variance.sqrt()
+}
+
+/// Largest drop from a running peak, as a fraction of the peak.
+pub fn max_drawdown(prices: &[f64]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut max_drawdown: f64 = 0.0;
+    for &price in prices {
+        peak = peak.max(price);
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max((peak - price) / peak);
+        }
+    }
+
+    max_drawdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() <= tolerance, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_moving_averages() {
+        assert_eq!(sma(&[1.0, 2.0, 3.0, 4.0, 5.0], 3), vec![2.0, 3.0, 4.0]);
+        assert!(sma(&[1.0, 2.0], 3).is_empty());
+
+        // Reference values from the StockCharts EMA example
+        let prices = [
+            22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39,
+        ];
+        let ema = ema(&prices, 10);
+        assert_eq!(ema.len(), 3);
+        assert_close(ema[0], 22.221, 1e-9);
+        assert_close(ema[1], 22.208, 1e-3);
+        assert_close(ema[2], 22.241, 1e-3);
+    }
+
+    #[test]
+    fn test_rsi() {
+        // Reference values from the StockCharts RSI example, which rounds the
+        // average gains and losses at every step
+        let prices = [
+            44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03,
+            45.61, 46.28, 46.28, 46.00,
+        ];
+        let values = rsi(&prices, 14);
+        assert_eq!(values.len(), 2);
+        assert_close(values[0], 70.53, 0.1);
+        assert_close(values[1], 66.32, 0.1);
+
+        assert_eq!(rsi(&[1.0, 2.0, 3.0], 2), vec![100.0]);
+    }
+
+    #[test]
+    fn test_macd() {
+        let flat = vec![10.0; 40];
+        let values = macd(&flat, 12, 26, 9);
+        assert_eq!(values.len(), 40 - 26 - 9 + 2);
+        assert!(values.iter().all(|v| v.macd == 0.0 && v.histogram == 0.0));
+
+        let rising = (0..40).map(|i| i as f64).collect::<Vec<_>>();
+        let last = *macd(&rising, 12, 26, 9).last().unwrap();
+        // The EMA of a linear series lags it by (period - 1) / 2
+        assert_close(last.macd, 7.0, 1e-6);
+        assert_close(last.histogram, 0.0, 1e-6);
+    }
+
+    #[test]
+    fn test_bollinger() {
+        let prices = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let bands = bollinger(&prices, 8, 2.0);
+        assert_eq!(
+            bands,
+            vec![BollingerBand {
+                lower: 1.0,
+                middle: 5.0,
+                upper: 9.0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_volatility_and_drawdown() {
+        let e = std::f64::consts::E;
+        assert_close(realized_volatility(&[1.0, e, 1.0, e]), 1.154_700_538, 1e-9);
+        assert_eq!(realized_volatility(&[1.0, 2.0]), 0.0);
+
+        assert_eq!(max_drawdown(&[1.0, 2.0, 1.0, 3.0, 1.5]), 0.5);
+        assert_eq!(max_drawdown(&[1.0, 2.0, 3.0]), 0.0);
+    }
+}
@@ -0,0 +1,208 @@
+//! Write-ahead journal of the token swaps of the portfolio.
+//!
+//! An intent is recorded before a swap, marked submitted with the signature
+//! of the transaction right before it is sent, and completed once the holding
+//! is updated. A crash in between leaves the intent unsettled, and it is
+//! reconciled against the chain on the next start.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Signature;
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::actions::utils::get_cur_timestamp;
+use crate::paper::trading_prefix;
+use crate::store::{LocalStore, Record, RecordMap};
+use crate::token::structs::TokenInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntentKind {
+    /// Swap `in_amount` lamports to the token
+    Buy,
+    /// Swap `in_amount` raw tokens to SOL
+    Sell,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum IntentState {
+    /// Recorded, the transaction is not sent yet
+    Pending,
+    /// The transaction may have been sent, `expected_out` is the quoted output
+    Submitted { tx_sig: String, expected_out: u64 },
+    /// The holding is updated with the swap
+    Completed { tx_sig: String, out_amount: u64 },
+    /// The swap never landed
+    Failed { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeIntent {
+    pub id: u64,
+    pub created_at: u64,
+    pub kind: IntentKind,
+    pub token_info: TokenInfo,
+    pub in_amount: u64,
+    pub state: IntentState,
+}
+
+impl TradeIntent {
+    pub fn is_settled(&self) -> bool {
+        matches!(
+            self.state,
+            IntentState::Completed { .. } | IntentState::Failed { .. }
+        )
+    }
+
+    pub fn signature(&self) -> Option<Signature> {
+        match &self.state {
+            IntentState::Submitted { tx_sig, .. } | IntentState::Completed { tx_sig, .. } => {
+                Signature::from_str(tx_sig).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for TradeIntent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Intent {}: {:?} {} with {}, {:?}",
+            self.id, self.kind, self.token_info.symbol, self.in_amount, self.state
+        )
+    }
+}
+
+impl Record for TradeIntent {
+    fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub struct TradeJournal {
+    intents: RecordMap<TradeIntent, LocalStore>,
+}
+
+impl TradeJournal {
+    const TRADE_INTENTS_PREFIX: &'static str = "trade_intents";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<TradeJournal> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            intents: RecordMap::new(trading_prefix(Self::TRADE_INTENTS_PREFIX)),
+        }
+    }
+
+    fn save(&self, intent: &TradeIntent) -> Result<()> {
+        self.intents.save(intent)
+    }
+
+    /// Records a pending intent before its swap is prepared.
+    pub fn open(
+        &self,
+        kind: IntentKind,
+        token_info: &TokenInfo,
+        in_amount: u64,
+    ) -> Result<TradeIntent> {
+        let intent = TradeIntent {
+            id: self.intents.next_id()?,
+            created_at: get_cur_timestamp(),
+            kind,
+            token_info: token_info.clone(),
+            in_amount,
+            state: IntentState::Pending,
+        };
+        self.save(&intent)?;
+        Ok(intent)
+    }
+
+    /// Records the signature of the swap, must be called before sending it.
+    pub fn submit(
+        &self,
+        intent: &mut TradeIntent,
+        tx_sig: &Signature,
+        expected_out: u64,
+    ) -> Result<()> {
+        intent.state = IntentState::Submitted {
+            tx_sig: tx_sig.to_string(),
+            expected_out,
+        };
+        self.save(intent)
+    }
+
+    pub fn complete(
+        &self,
+        intent: &mut TradeIntent,
+        tx_sig: &Signature,
+        out_amount: u64,
+    ) -> Result<()> {
+        intent.state = IntentState::Completed {
+            tx_sig: tx_sig.to_string(),
+            out_amount,
+        };
+        self.save(intent)
+    }
+
+    pub fn fail(&self, intent: &mut TradeIntent, reason: impl ToString) -> Result<()> {
+        intent.state = IntentState::Failed {
+            reason: reason.to_string(),
+        };
+        self.save(intent)
+    }
+
+    /// All intents, oldest first.
+    pub fn list(&self) -> Vec<TradeIntent> {
+        self.intents.list()
+    }
+
+    /// Intents neither completed nor failed.
+    pub fn unsettled(&self) -> Vec<TradeIntent> {
+        self.list()
+            .into_iter()
+            .filter(|intent| !intent.is_settled())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_intent_states() {
+        let mut intent = TradeIntent {
+            id: 0,
+            created_at: 0,
+            kind: IntentKind::Buy,
+            token_info: TokenInfo {
+                address: Pubkey::default(),
+                symbol: "TEST".to_string(),
+                name: "Test".to_string(),
+                decimals: 6,
+                coingecko_id: None,
+            },
+            in_amount: 1_000,
+            state: IntentState::Pending,
+        };
+        assert!(!intent.is_settled());
+        assert_eq!(intent.signature(), None);
+
+        let sig = Signature::new_unique();
+        intent.state = IntentState::Submitted {
+            tx_sig: sig.to_string(),
+            expected_out: 10,
+        };
+        assert!(!intent.is_settled());
+        assert_eq!(intent.signature(), Some(sig));
+
+        let raw = serde_json::to_string(&intent).unwrap();
+        let intent: TradeIntent = serde_json::from_str(&raw).unwrap();
+        assert_eq!(intent.signature(), Some(sig));
+    }
+}
@@ -1,2 +1,3 @@
+pub(crate) mod quote;
 pub(crate) mod swap;
 pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
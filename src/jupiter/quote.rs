@@ -0,0 +1,84 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use std::str::FromStr;
+
+use crate::client::get_http_client;
+
+/// A Jupiter swap quote.
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub in_amount: u64,
+    pub out_amount: u64,
+    /// Estimated price impact of the swap, as a fraction
+    pub price_impact: f64,
+    /// Raw quote response, required to build the swap transaction
+    pub response: serde_json::Value,
+}
+
+pub async fn quote(
+    input_mint: &str,
+    output_mint: &str,
+    amount: u64,
+    slippage_bps: u64,
+) -> Result<Quote> {
+    // Define the quote API endpoint and parameters
+    let quote_url = "https://quote-api.jup.ag/v6/quote";
+    let amount_str = amount.to_string();
+    let slippage_bps = slippage_bps.to_string();
+    let params = [
+        ("inputMint", input_mint),
+        ("outputMint", output_mint),
+        ("amount", &amount_str),
+        ("slippageBps", &slippage_bps),
+    ];
+
+    let http_client = get_http_client();
+
+    // Make GET request to fetch the quote
+    // Doc: https://station.jup.ag/api-v6/get-quote
+    let response: serde_json::Value = http_client
+        .get(quote_url)
+        .query(&params)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    let out_amount = response
+        .get("outAmount")
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or(anyhow::anyhow!(
+            "Failed to parse outAmount from the response"
+        ))?;
+
+    let price_impact = response
+        .get("priceImpactPct")
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<f64>().ok())
+        .ok_or(anyhow::anyhow!(
+            "Failed to parse priceImpactPct from the response"
+        ))?;
+
+    Ok(Quote {
+        in_amount: amount,
+        out_amount,
+        price_impact,
+        response,
+    })
+}
+
+impl Quote {
+    /// Pools of the route, the accounts the swap writes to.
+    pub fn route_accounts(&self) -> Vec<Pubkey> {
+        self.response
+            .get("routePlan")
+            .and_then(|plan| plan.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|step| step["swapInfo"]["ammKey"].as_str())
+            .filter_map(|key| Pubkey::from_str(key).ok())
+            .collect()
+    }
+}
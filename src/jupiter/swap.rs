@@ -1,10 +1,12 @@
 use anyhow::Result;
 use base64::{engine::general_purpose, Engine as _};
 use serde::Serialize;
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction};
 
-use super::SOL_MINT;
+use super::quote::quote;
+use crate::swap::params::{PriorityFees, SwapSettings};
+use crate::swap::sender::TransactionSender;
+use crate::swap::PreparedSwap;
 use crate::wallet::Wallet;
 use crate::{client::get_http_client, config::Config};
 
@@ -18,74 +20,47 @@ struct SwapRequest<'a> {
     user_public_key: String,
     #[serde(rename = "wrapAndUnwrapSol")]
     wrap_and_unwrap_sol: bool,
-    #[serde(rename = "prioritizationFeeLamports")]
-    prioritization_fee_lamports: u64,
+    #[serde(rename = "computeUnitPriceMicroLamports")]
+    compute_unit_price_micro_lamports: u64,
 }
 
-pub async fn swap_from_sol(output_mint: &str, amount: u64) -> Result<(u64, Signature)> {
-    swap(SOL_MINT, output_mint, amount).await
-}
-
-pub async fn swap_to_sol(input_mint: &str, amount: u64) -> Result<(u64, Signature)> {
-    swap(input_mint, SOL_MINT, amount).await
-}
-
-pub async fn swap(input_mint: &str, output_mint: &str, amount: u64) -> Result<(u64, Signature)> {
-    let config = Config::get();
+/// Quotes the swap and signs its transaction.
+pub async fn prepare_swap(
+    input_mint: &Pubkey,
+    output_mint: &Pubkey,
+    amount: u64,
+    slippage_bps: u64,
+) -> Result<PreparedSwap> {
     let wallet = Wallet::get();
-    let jupiter_rpc_url = &config.jupiter_rpc_url;
-
-    let rpc_client = RpcClient::new(jupiter_rpc_url.into());
-
-    // Define the quote API endpoint and parameters
-    let quote_url = "https://quote-api.jup.ag/v6/quote";
-    let amount_str = amount.to_string();
-    let params = [
-        ("inputMint", input_mint),
-        ("outputMint", output_mint),
-        ("amount", &amount_str),
-        ("slippageBps", "300"), // 3% slippage
-    ];
-
-    let http_client = get_http_client();
-
-    // Make GET request to fetch the quote
-    // Doc: https://station.jup.ag/api-v6/get-quote
-    let quote_response: serde_json::Value = http_client
-        .get(quote_url)
-        .query(&params)
-        .send()
-        .await?
-        .json()
-        .await?;
 
-    let out_amount = quote_response
-        .get("outAmount")
-        .and_then(|v| v.as_str())
-        .and_then(|v| v.parse::<u64>().ok())
-        .ok_or(anyhow::anyhow!(
-            "Failed to parse outAmount from the response"
-        ))?;
+    let quote = quote(
+        &input_mint.to_string(),
+        &output_mint.to_string(),
+        amount,
+        slippage_bps,
+    )
+    .await?;
+    let out_amount = quote.out_amount;
+    let compute_unit_price =
+        PriorityFees::from_config(Config::get()).estimate(&quote.route_accounts());
 
     tracing::info!(
-        "Swapping: inAmount {}, outAmount {}, inputMint {}, outputMint {}",
+        "Swapping: inAmount {}, outAmount {}, inputMint {}, outputMint {}, slippageBps {}, computeUnitPrice {}",
         amount,
         out_amount,
         input_mint,
-        output_mint
+        output_mint,
+        slippage_bps,
+        compute_unit_price
     );
 
-    if config.mock_trade {
-        // tracing::info!("Mock trade enabled. Skipping swap.");
-        return Ok((out_amount, Signature::default()));
-    }
-
     let swap_url = "https://quote-api.jup.ag/v6/swap";
+    let http_client = get_http_client();
     let swap_request = SwapRequest {
-        quote_response: &quote_response,
+        quote_response: &quote.response,
         user_public_key: wallet.pubkey().to_string(),
         wrap_and_unwrap_sol: true,
-        prioritization_fee_lamports: 200000,
+        compute_unit_price_micro_lamports: compute_unit_price,
     };
 
     // Make POST request to fetch the swap transaction
@@ -116,17 +91,37 @@ pub async fn swap(input_mint: &str, output_mint: &str, amount: u64) -> Result<(u
         .map_err(|_| anyhow::anyhow!("Failed to deserialize swapTransaction"))?;
 
     let signed_versioned_tx =
-        VersionedTransaction::try_new(versioned_tx.message, &[wallet.keypair()]).unwrap();
+        VersionedTransaction::try_new(versioned_tx.message, &[wallet.keypair()])?;
 
-    // Send the transaction to the Solana network
-    let txid = rpc_client
-        .send_and_confirm_transaction(&signed_versioned_tx)
-        .await?;
+    Ok(PreparedSwap {
+        input_mint: *input_mint,
+        output_mint: *output_mint,
+        in_amount: amount,
+        out_amount,
+        price_impact: Some(quote.price_impact),
+        settings: SwapSettings {
+            slippage_bps,
+            compute_unit_price,
+        },
+        transaction: signed_versioned_tx,
+    })
+}
 
+/// Simulates a prepared swap without sending it.
+pub async fn simulate_swap(prepared: &PreparedSwap) -> Result<()> {
+    let sender = TransactionSender::new(Config::get().jupiter_rpc_url.clone());
+    let units_consumed = sender.simulate(&prepared.transaction).await?;
     tracing::info!(
-        "Transaction confirmed. View on Solscan: https://solscan.io/tx/{}",
-        txid
+        "Simulated {}: {} compute units",
+        prepared.signature(),
+        units_consumed
     );
 
-    Ok((out_amount, txid))
+    Ok(())
+}
+
+/// Sends a simulated swap and waits for its confirmation.
+pub async fn send_swap(prepared: &PreparedSwap) -> Result<Signature> {
+    let sender = TransactionSender::new(Config::get().jupiter_rpc_url.clone());
+    Ok(sender.send(&prepared.transaction).await?)
 }
@@ -1,17 +1,34 @@
 mod actions;
 mod attest;
+mod backtest;
+mod buyback;
 mod client;
 mod config;
 mod constant;
+mod cost_basis;
+mod exit;
 mod feed;
+mod fill;
+mod indicators;
+mod journal;
 mod jupiter;
 mod llm;
+mod nav;
+mod orders;
+mod paper;
 mod pipeline;
 mod portfolio;
 mod price;
+mod rebalance;
+mod reconcile;
+mod risk;
+mod round;
 pub mod store;
 mod strategy;
+mod swap;
+mod tax;
 mod token;
+mod twap;
 mod twitter;
 mod wallet;
 
@@ -24,6 +41,8 @@ use crate::config::Config;
 use crate::constant::*;
 use crate::feed::substack::SubstackFeed;
 use crate::pipeline::PipelineBuilder;
+use crate::portfolio::Portfolio;
+use crate::reconcile::reconcile_holdings;
 use crate::token::jimmy::JimmyToken;
 use crate::twitter::TwitterClient;
 use crate::wallet::Wallet;
@@ -32,6 +51,46 @@ use crate::wallet::Wallet;
 async fn main() -> Result<()> {
     setup_env_and_tracing();
 
+    // Offline backtest, no wallet needed
+    if std::env::args().nth(1).as_deref() == Some("backtest") {
+        let dir = std::env::args()
+            .nth(2)
+            .unwrap_or_else(|| "assets/backtest".into());
+        backtest::run_fixtures(dir)?;
+        return Ok(());
+    }
+
+    // Trading round records, `jimmy rounds [round id]`
+    if std::env::args().nth(1).as_deref() == Some("rounds") {
+        round::inspect(std::env::args().nth(2))?;
+        return Ok(());
+    }
+
+    // Tax-lot report as CSV, `jimmy tax-lots [output file]`
+    if std::env::args().nth(1).as_deref() == Some("tax-lots") {
+        tax::export(std::env::args().nth(2))?;
+        return Ok(());
+    }
+
+    // Performance over the NAV history, `jimmy performance [days]`
+    if std::env::args().nth(1).as_deref() == Some("performance") {
+        nav::report(std::env::args().nth(2))?;
+        return Ok(());
+    }
+
+    // Limit orders, `jimmy orders [buy|sell <token> <amount> <price> [ttl hours] | cancel <id>]`
+    if std::env::args().nth(1).as_deref() == Some("orders") {
+        orders::manage(&std::env::args().skip(2).collect::<Vec<_>>()).await?;
+        return Ok(());
+    }
+
+    // Exit rules of the holdings,
+    // `jimmy exits [<token> <stop loss> <trailing stop> <take profit> | reset <token>]`
+    if std::env::args().nth(1).as_deref() == Some("exits") {
+        exit::manage(&std::env::args().skip(2).collect::<Vec<_>>()).await?;
+        return Ok(());
+    }
+
     let wallet = Wallet::get();
 
     let user_report = wallet.pubkey().to_bytes();
@@ -54,7 +113,10 @@ async fn main() -> Result<()> {
 
     let config = Config::get();
     let min_sol_balance_lamports = config.min_sol_balance_lamports();
-    while wallet.balance()? < min_sol_balance_lamports {
+    if config.paper_trading {
+        tracing::info!("Paper trading enabled, the wallet is never traded");
+    }
+    while !config.paper_trading && wallet.balance()? < min_sol_balance_lamports {
         tracing::info!("Waiting for enough SOL...");
         tracing::info!(
             "Please transfer at least {} SOL to Jimmy's wallet: {}",
@@ -76,12 +138,20 @@ async fn main() -> Result<()> {
     tracing::info!("Current SOL balance: {} lamports", balance);
 
     let jimmy_token = JimmyToken::get().await;
-    jimmy_token.print_metadata()?;
-    jimmy_token.print_balance()?;
+    if !config.paper_trading {
+        jimmy_token.print_metadata()?;
+        jimmy_token.print_balance()?;
+    }
 
     let balance = wallet.balance()?;
     tracing::info!("Current SOL balance: {} lamports", balance);
 
+    // Settle the swaps a crash left unsettled before trading again
+    Portfolio::get().await.reconcile_intents().await?;
+    if let Err(e) = reconcile_holdings().await {
+        tracing::error!("Failed to reconcile holdings: {}", e);
+    }
+
     let pipeline = PipelineBuilder::new()
         .with_feed(SubstackFeed::new())
         // .with_feed(NewsletterFeed::new())
@@ -0,0 +1,412 @@
+//! Periodic snapshots of the net asset value and the performance analytics
+//! of the portfolio over them.
+//!
+//! Returns are measured on the NAV in USD, so deposits and withdrawals of
+//! the wallet show up as gains and losses. The JIMMY inventory, mostly minted
+//! supply, is reported apart and left out of the NAV.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::utils::get_cur_timestamp;
+use crate::actions::Action;
+use crate::config::Config;
+use crate::constant::*;
+use crate::indicators::max_drawdown;
+use crate::paper::trading_prefix;
+use crate::portfolio::Portfolio;
+use crate::store::{LocalStore, Store, StoreMap};
+
+const SECS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoldingSnapshot {
+    pub token: String,
+    pub symbol: String,
+    /// Raw amount held
+    pub amount: u64,
+    /// Value in lamports
+    pub value: f64,
+}
+
+/// The SOL balance, every holding and the JIMMY inventory at one time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NavSnapshot {
+    /// UNIX timestamp in seconds
+    pub timestamp: u64,
+    pub sol_balance: u64,
+    pub holdings: Vec<HoldingSnapshot>,
+    /// Raw JIMMY held
+    pub jimmy_amount: u64,
+    /// Value of the JIMMY inventory in lamports at the current pool price,
+    /// None when it could not be priced
+    pub jimmy_value: Option<f64>,
+    pub sol_price_usd: f64,
+}
+
+impl NavSnapshot {
+    /// Net asset value in lamports, JIMMY inventory excluded
+    pub fn nav(&self) -> f64 {
+        self.sol_balance as f64 + self.holdings.iter().map(|h| h.value).sum::<f64>()
+    }
+
+    pub fn nav_usd(&self) -> f64 {
+        self.nav() / LAMPORTS_PER_SOL as f64 * self.sol_price_usd
+    }
+}
+
+impl std::fmt::Display for NavSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "NAV at {}: {:.4} SOL, {:.2} USD, {} holdings",
+            self.timestamp,
+            self.nav() / LAMPORTS_PER_SOL as f64,
+            self.nav_usd(),
+            self.holdings.len()
+        )?;
+        if let Some(jimmy_value) = self.jimmy_value {
+            write!(
+                f,
+                ", JIMMY inventory {:.4} SOL",
+                jimmy_value / LAMPORTS_PER_SOL as f64
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Values the portfolio now.
+pub async fn take_snapshot() -> Result<NavSnapshot> {
+    let portfolio = Portfolio::get().await;
+    let valuation = portfolio.valuation().await?;
+    let config = Config::get();
+
+    let jimmy_amount = portfolio.jimmy_balance().await?;
+    let jimmy_ui_amount = jimmy_amount as f64 / 10_f64.powi(config.token_decimals as i32);
+    let jimmy_value = match portfolio.jimmy_price_in_sol().await {
+        Ok(price) => Some(jimmy_ui_amount * price * LAMPORTS_PER_SOL as f64),
+        Err(e) => {
+            tracing::warn!("No price of the JIMMY inventory: {}", e);
+            None
+        }
+    };
+    let holdings = valuation
+        .holdings
+        .iter()
+        .map(|h| HoldingSnapshot {
+            token: h.holding.token_info.address.to_string(),
+            symbol: h.holding.token_info.symbol.clone(),
+            amount: h.holding.holding_amount(),
+            value: h.value(),
+        })
+        .collect();
+
+    Ok(NavSnapshot {
+        timestamp: get_cur_timestamp(),
+        sol_balance: valuation.sol_balance,
+        holdings,
+        jimmy_amount,
+        jimmy_value,
+        sol_price_usd: valuation.sol_price_usd,
+    })
+}
+
+/// The [`NavSnapshot`]s, stored as JSON under their big-endian timestamp so
+/// they iterate in order.
+pub struct NavHistory {
+    snapshots: StoreMap<[u8; 8], String, LocalStore>,
+}
+
+impl NavHistory {
+    const NAV_SNAPSHOTS_PREFIX: &'static str = "nav_snapshots";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<NavHistory> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            snapshots: LocalStore::open_map(trading_prefix(Self::NAV_SNAPSHOTS_PREFIX)),
+        }
+    }
+
+    pub fn insert(&self, snapshot: &NavSnapshot) -> Result<()> {
+        self.snapshots.insert(
+            snapshot.timestamp.to_be_bytes(),
+            serde_json::to_string(snapshot)?,
+        )
+    }
+
+    /// Snapshots taken at or after `since`, oldest first.
+    pub fn since(&self, since: u64) -> Vec<NavSnapshot> {
+        self.snapshots
+            .iter()
+            .filter(|(timestamp, _)| u64::from_be_bytes(**timestamp) >= since)
+            .filter_map(|(_, raw)| serde_json::from_str(&raw).ok())
+            .collect()
+    }
+
+    /// Takes a snapshot and stores it.
+    pub async fn record(&self) -> Result<NavSnapshot> {
+        let snapshot = take_snapshot().await?;
+        self.insert(&snapshot)?;
+        tracing::info!("{}", snapshot);
+        Ok(snapshot)
+    }
+}
+
+/// A position bought and then sold down to nothing, amounts in lamports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedPosition {
+    pub token: String,
+    pub closed_at: u64,
+    pub cost: f64,
+    pub proceeds: f64,
+}
+
+impl ClosedPosition {
+    pub fn pnl(&self) -> f64 {
+        self.proceeds - self.cost
+    }
+}
+
+#[derive(Default)]
+struct OpenPosition {
+    quantity: u64,
+    cost: f64,
+    proceeds: f64,
+}
+
+/// Replays the portfolio actions, with their timestamps in order, into the
+/// positions they closed. Costs and proceeds are net of the fees when known.
+pub fn closed_positions(
+    actions: impl IntoIterator<Item = (PortfolioAction, u64)>,
+) -> Vec<ClosedPosition> {
+    let mut open: HashMap<String, OpenPosition> = HashMap::new();
+    let mut closed = vec![];
+
+    for (action, timestamp) in actions {
+        match action {
+            PortfolioAction::Buy {
+                token,
+                amount,
+                sol,
+                fill,
+                ..
+            } => {
+                let position = open.entry(token).or_default();
+                position.quantity += amount;
+                position.cost += fill.map(|fill| fill.net_sol).unwrap_or(sol) as f64;
+            }
+            PortfolioAction::Sell {
+                token,
+                amount,
+                sol,
+                fill,
+                ..
+            } => {
+                // Sales of tokens never bought, such as minted JIMMY, open nothing
+                let Some(position) = open.get_mut(&token) else {
+                    continue;
+                };
+                position.quantity = position.quantity.saturating_sub(amount);
+                position.proceeds += fill.map(|fill| fill.net_sol).unwrap_or(sol) as f64;
+                if position.quantity == 0 {
+                    let position = open.remove(&token).unwrap_or_default();
+                    closed.push(ClosedPosition {
+                        token,
+                        closed_at: timestamp,
+                        cost: position.cost,
+                        proceeds: position.proceeds,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    closed
+}
+
+/// Performance of the portfolio between the first and last snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Performance {
+    pub from: u64,
+    pub to: u64,
+    pub snapshots: usize,
+    /// Return of the NAV in USD
+    pub cumulative_return: f64,
+    /// Return of the NAV in SOL
+    pub sol_return: f64,
+    /// Return of holding the initial NAV in SOL, in USD
+    pub benchmark_return: f64,
+    /// Annualized, with a risk-free rate of 0
+    pub sharpe: f64,
+    /// Annualized, with a risk-free rate of 0
+    pub sortino: f64,
+    pub max_drawdown: f64,
+    pub closed_positions: usize,
+    /// Share of the closed positions with a positive PnL
+    pub win_rate: Option<f64>,
+}
+
+impl Performance {
+    /// `snapshots` are oldest first, and `closed` the positions closed
+    /// between the first and last of them. None without two snapshots.
+    pub fn compute(snapshots: &[NavSnapshot], closed: &[ClosedPosition]) -> Option<Self> {
+        let (first, last) = match snapshots {
+            [first, .., last] => (first, last),
+            _ => return None,
+        };
+        let rate = |from: f64, to: f64| if from > 0.0 { to / from - 1.0 } else { 0.0 };
+
+        let navs = snapshots.iter().map(|s| s.nav_usd()).collect::<Vec<_>>();
+        let returns = navs
+            .windows(2)
+            .map(|w| rate(w[0], w[1]))
+            .collect::<Vec<_>>();
+        let periods_per_year = SECS_PER_YEAR * returns.len() as f64
+            / last.timestamp.saturating_sub(first.timestamp).max(1) as f64;
+
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let deviation = |downside: bool| {
+            let squares = returns
+                .iter()
+                .map(|r| if downside { r.min(0.0) } else { r - mean })
+                .map(|d| d * d)
+                .sum::<f64>();
+            (squares / returns.len() as f64).sqrt()
+        };
+        let annualized = |deviation: f64| {
+            if deviation > 0.0 {
+                mean / deviation * periods_per_year.sqrt()
+            } else {
+                0.0
+            }
+        };
+
+        let wins = closed.iter().filter(|p| p.pnl() > 0.0).count();
+        Some(Self {
+            from: first.timestamp,
+            to: last.timestamp,
+            snapshots: snapshots.len(),
+            cumulative_return: rate(first.nav_usd(), last.nav_usd()),
+            sol_return: rate(first.nav(), last.nav()),
+            benchmark_return: rate(first.sol_price_usd, last.sol_price_usd),
+            sharpe: annualized(deviation(false)),
+            sortino: annualized(deviation(true)),
+            max_drawdown: max_drawdown(&navs),
+            closed_positions: closed.len(),
+            win_rate: (!closed.is_empty()).then(|| wins as f64 / closed.len() as f64),
+        })
+    }
+}
+
+impl std::fmt::Display for Performance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Performance from {} to {} over {} snapshots: return {:.2}% ({:.2}% in SOL), SOL buy-and-hold {:.2}%, Sharpe {:.2}, Sortino {:.2}, max drawdown {:.2}%, {} closed positions",
+            self.from,
+            self.to,
+            self.snapshots,
+            self.cumulative_return * 100.0,
+            self.sol_return * 100.0,
+            self.benchmark_return * 100.0,
+            self.sharpe,
+            self.sortino,
+            self.max_drawdown * 100.0,
+            self.closed_positions,
+        )?;
+        if let Some(win_rate) = self.win_rate {
+            write!(f, ", win rate {:.2}%", win_rate * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the performance over the last `days`, 30 by default.
+pub fn report(days: Option<String>) -> Result<()> {
+    let days: u64 = days.map(|d| d.parse()).transpose()?.unwrap_or(30);
+    let since = get_cur_timestamp().saturating_sub(days * 24 * 60 * 60);
+    let snapshots = NavHistory::get().since(since);
+
+    let mut actions = PortfolioAction::iter()
+        .map(|(action, raw)| (action, raw.timestamp()))
+        .collect::<Vec<_>>();
+    actions.sort_by_key(|(_, timestamp)| *timestamp);
+    let closed = closed_positions(actions)
+        .into_iter()
+        .filter(|p| {
+            snapshots
+                .first()
+                .is_some_and(|s| p.closed_at >= s.timestamp)
+        })
+        .collect::<Vec<_>>();
+
+    match Performance::compute(&snapshots, &closed) {
+        Some(performance) => tracing::info!("{}", performance),
+        None => tracing::info!("Not enough NAV snapshots over the last {} days", days),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn snapshot(day: u64, sol: f64, sol_price_usd: f64) -> NavSnapshot {
+        NavSnapshot {
+            timestamp: day * DAY,
+            sol_balance: (sol * LAMPORTS_PER_SOL as f64) as u64,
+            sol_price_usd,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_performance() {
+        let snapshots = vec![
+            snapshot(0, 100.0, 10.0),
+            snapshot(1, 110.0, 10.0),
+            snapshot(2, 99.0, 10.0),
+            snapshot(3, 120.0, 11.0),
+        ];
+        let actions = vec![
+            (PortfolioAction::buy("A".into(), 100, 10, String::new()), 0),
+            (PortfolioAction::buy("B".into(), 100, 10, String::new()), 0),
+            (PortfolioAction::sell("A".into(), 60, 9, String::new()), 1),
+            (PortfolioAction::sell("A".into(), 40, 6, String::new()), 2),
+            (PortfolioAction::sell("B".into(), 100, 8, String::new()), 2),
+        ];
+        let closed = closed_positions(actions);
+        assert_eq!(closed.len(), 2);
+        assert_eq!((closed[0].token.as_str(), closed[0].pnl()), ("A", 5.0));
+
+        let performance = Performance::compute(&snapshots, &closed).unwrap();
+        assert!((performance.cumulative_return - 0.32).abs() < 1e-9);
+        assert!((performance.sol_return - 0.2).abs() < 1e-9);
+        assert!((performance.benchmark_return - 0.1).abs() < 1e-9);
+        assert!((performance.max_drawdown - 0.1).abs() < 1e-9);
+        assert!(performance.sharpe > 0.0 && performance.sortino > performance.sharpe);
+        assert_eq!(performance.win_rate, Some(0.5));
+
+        assert!(Performance::compute(&snapshots[..1], &closed).is_none());
+
+        // The JIMMY inventory stays out of the NAV
+        let with_jimmy = NavSnapshot {
+            jimmy_value: Some(1e12),
+            ..snapshots[0].clone()
+        };
+        assert_eq!(with_jimmy.nav(), snapshots[0].nav());
+    }
+}
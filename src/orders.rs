@@ -0,0 +1,394 @@
+//! Limit orders of the agent: buy a token once its price falls to a limit,
+//! or sell it once its price rises to one, until the order expires.
+//!
+//! Orders are checked on a fast cadence against the aggregated prices and
+//! executed at market through the portfolio once triggered.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::utils::get_cur_timestamp;
+use crate::actions::Action;
+use crate::constant::*;
+use crate::paper::trading_prefix;
+use crate::portfolio::Portfolio;
+use crate::store::{LocalStore, Record, RecordMap};
+use crate::swap::sender::SendError;
+use crate::token::store::SolanaTokenStore;
+use crate::token::structs::TokenInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderSide {
+    /// Spend `amount` lamports on the token at or below the limit price
+    Buy,
+    /// Sell `amount` raw tokens at or above the limit price
+    Sell,
+}
+
+impl std::fmt::Display for OrderSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderSide::Buy => write!(f, "buy"),
+            OrderSide::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+impl FromStr for OrderSide {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "buy" => Ok(OrderSide::Buy),
+            "sell" => Ok(OrderSide::Sell),
+            _ => Err(anyhow::anyhow!("Unknown order side: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderStatus {
+    Open,
+    /// Executed when the price reached `price_in_sol`
+    Filled {
+        price_in_sol: f64,
+    },
+    Expired,
+    Cancelled,
+    /// Triggered, but the swap failed for good
+    Failed {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitOrder {
+    pub id: u64,
+    pub created_at: u64,
+    pub side: OrderSide,
+    pub token_info: TokenInfo,
+    /// Lamports to spend for a buy, raw tokens to sell for a sell
+    pub amount: u64,
+    /// Price of one UI unit of the token, in SOL
+    pub limit_price: f64,
+    /// UNIX timestamp in seconds the order lapses at, never if unset
+    pub expires_at: Option<u64>,
+    pub status: OrderStatus,
+}
+
+impl LimitOrder {
+    pub fn is_open(&self) -> bool {
+        self.status == OrderStatus::Open
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// Whether `price_in_sol` reached the limit price.
+    pub fn is_triggered(&self, price_in_sol: f64) -> bool {
+        if price_in_sol <= 0.0 {
+            return false;
+        }
+        match self.side {
+            OrderSide::Buy => price_in_sol <= self.limit_price,
+            OrderSide::Sell => price_in_sol >= self.limit_price,
+        }
+    }
+}
+
+impl std::fmt::Display for LimitOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = match self.side {
+            OrderSide::Buy => "lamports",
+            OrderSide::Sell => "raw tokens",
+        };
+        write!(
+            f,
+            "Order {}: {} {} {} of {} at {} SOL, expires at {:?}, {:?}",
+            self.id,
+            self.side,
+            self.amount,
+            unit,
+            self.token_info.symbol,
+            self.limit_price,
+            self.expires_at,
+            self.status
+        )
+    }
+}
+
+impl Record for LimitOrder {
+    fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub struct LimitOrders {
+    orders: RecordMap<LimitOrder, LocalStore>,
+}
+
+impl LimitOrders {
+    const LIMIT_ORDERS_PREFIX: &'static str = "limit_orders";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<LimitOrders> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            orders: RecordMap::new(trading_prefix(Self::LIMIT_ORDERS_PREFIX)),
+        }
+    }
+
+    fn save(&self, order: &LimitOrder) -> Result<()> {
+        self.orders.save(order)
+    }
+
+    /// Places an order of `amount` lamports for a buy or raw tokens for a
+    /// sell, lapsing after `ttl_secs` if set.
+    pub fn place(
+        &self,
+        side: OrderSide,
+        token_info: &TokenInfo,
+        amount: u64,
+        limit_price: f64,
+        ttl_secs: Option<u64>,
+    ) -> Result<LimitOrder> {
+        if amount == 0 || limit_price <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Invalid order of {} at {} SOL",
+                amount,
+                limit_price
+            ));
+        }
+
+        let created_at = get_cur_timestamp();
+        let order = LimitOrder {
+            id: self.orders.next_id()?,
+            created_at,
+            side,
+            token_info: token_info.clone(),
+            amount,
+            limit_price,
+            expires_at: ttl_secs.map(|ttl_secs| created_at + ttl_secs),
+            status: OrderStatus::Open,
+        };
+        self.save(&order)?;
+        tracing::info!("Placed {}", order);
+
+        Ok(order)
+    }
+
+    pub fn cancel(&self, id: u64) -> Result<LimitOrder> {
+        let mut order = self
+            .orders
+            .get(id)?
+            .ok_or(anyhow::anyhow!("Order {} not found", id))?;
+        if !order.is_open() {
+            return Err(anyhow::anyhow!("Order {} is not open", id));
+        }
+
+        order.status = OrderStatus::Cancelled;
+        self.save(&order)?;
+        Ok(order)
+    }
+
+    /// All orders, oldest first.
+    pub fn list(&self) -> Vec<LimitOrder> {
+        self.orders.list()
+    }
+
+    pub fn open(&self) -> Vec<LimitOrder> {
+        self.list()
+            .into_iter()
+            .filter(|order| order.is_open())
+            .collect()
+    }
+
+    /// Expires the lapsed orders and executes those whose limit price was
+    /// reached. Orders failing on slippage or an expired blockhash stay
+    /// open for the next check.
+    pub async fn check(&self) -> Result<()> {
+        let portfolio = Portfolio::get().await;
+        let now = get_cur_timestamp();
+
+        for mut order in self.open() {
+            if order.is_expired(now) {
+                tracing::info!("Expired {}", order);
+                order.status = OrderStatus::Expired;
+                self.save(&order)?;
+                continue;
+            }
+
+            let token_info = &order.token_info;
+            let price_in_sol = match portfolio
+                .price_in_sol(&token_info.address, token_info.coingecko_id.as_deref())
+                .await
+            {
+                Ok(price_in_sol) => price_in_sol,
+                Err(e) => {
+                    tracing::warn!(
+                        "No price of {} for order {}: {}",
+                        token_info.symbol,
+                        order.id,
+                        e
+                    );
+                    continue;
+                }
+            };
+            if !order.is_triggered(price_in_sol) {
+                continue;
+            }
+
+            tracing::info!("Triggered at {} SOL: {}", price_in_sol, order);
+            PortfolioAction::order(
+                token_info.address.to_string(),
+                order.id,
+                order.side,
+                order.limit_price,
+                price_in_sol,
+            )
+            .log();
+
+            let executed = match order.side {
+                OrderSide::Buy => portfolio
+                    .buy_token(token_info, order.amount)
+                    .await
+                    .map(|_| ()),
+                OrderSide::Sell => {
+                    let holding_amount = portfolio
+                        .tokens()
+                        .get(&token_info.address)?
+                        .map(|holding| holding.holding_amount())
+                        .unwrap_or_default();
+                    match order.amount.min(holding_amount) {
+                        0 => Err(anyhow::anyhow!("No {} held to sell", token_info.symbol)),
+                        amount => portfolio.sell_token(token_info, amount).await,
+                    }
+                }
+            };
+            match executed {
+                Ok(()) => order.status = OrderStatus::Filled { price_in_sol },
+                Err(e) => {
+                    tracing::error!("Failed to execute order {}: {}", order.id, e);
+                    let retry = matches!(
+                        e.downcast_ref::<SendError>(),
+                        Some(SendError::SlippageExceeded | SendError::BlockhashExpired)
+                    );
+                    if retry {
+                        continue;
+                    }
+                    order.status = OrderStatus::Failed {
+                        reason: e.to_string(),
+                    };
+                }
+            }
+            self.save(&order)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Places, cancels or lists the limit orders,
+/// `jimmy orders [buy|sell <token> <amount> <price in SOL> [ttl hours] | cancel <id>]`.
+///
+/// The amount of a buy is in SOL, the amount of a sell in UI tokens.
+pub async fn manage(args: &[String]) -> Result<()> {
+    let orders = LimitOrders::get();
+    match args {
+        [] => {
+            for order in orders.list() {
+                tracing::info!("{}", order);
+            }
+        }
+        [cancel, id] if cancel == "cancel" => {
+            let order = orders.cancel(id.parse()?)?;
+            tracing::info!("Cancelled {}", order);
+        }
+        [side, token, amount, limit_price, ttl @ ..] if ttl.len() <= 1 => {
+            let side: OrderSide = side.parse()?;
+            let token_info = match Pubkey::from_str(token) {
+                Ok(mint) => {
+                    SolanaTokenStore::get()
+                        .get_token_info_by_mint(&mint)
+                        .await?
+                }
+                Err(_) => SolanaTokenStore::get().get_token_info(token).await?,
+            }
+            .ok_or(anyhow::anyhow!("Token {} not found", token))?;
+
+            let amount: f64 = amount.parse()?;
+            let amount = match side {
+                OrderSide::Buy => amount * LAMPORTS_PER_SOL as f64,
+                OrderSide::Sell => amount * 10_f64.powi(token_info.decimals as i32),
+            };
+            let ttl_secs = ttl
+                .first()
+                .map(|hours| hours.parse::<u64>())
+                .transpose()?
+                .map(|hours| hours * 60 * 60);
+            orders.place(
+                side,
+                &token_info,
+                amount as u64,
+                limit_price.parse()?,
+                ttl_secs,
+            )?;
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Usage: jimmy orders [buy|sell <token> <amount> <price in SOL> [ttl hours] | cancel <id>]"
+            ))
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(side: OrderSide, expires_at: Option<u64>) -> LimitOrder {
+        LimitOrder {
+            id: 0,
+            created_at: 0,
+            side,
+            token_info: TokenInfo {
+                address: Pubkey::new_unique(),
+                decimals: 6,
+                name: "Test".to_string(),
+                symbol: "TEST".to_string(),
+                coingecko_id: None,
+            },
+            amount: LAMPORTS_PER_SOL,
+            limit_price: 0.01,
+            expires_at,
+            status: OrderStatus::Open,
+        }
+    }
+
+    #[test]
+    fn test_limit_orders() {
+        let buy = order(OrderSide::Buy, Some(100));
+        assert!(buy.is_triggered(0.009) && buy.is_triggered(0.01));
+        assert!(!buy.is_triggered(0.011));
+        // A missing price never triggers
+        assert!(!buy.is_triggered(0.0));
+        assert!(!buy.is_expired(99) && buy.is_expired(100));
+
+        let sell = order(OrderSide::Sell, None);
+        assert!(sell.is_triggered(0.012) && !sell.is_triggered(0.009));
+        assert!(!sell.is_expired(u64::MAX));
+
+        assert_eq!("Sell".parse::<OrderSide>().unwrap(), OrderSide::Sell);
+        assert!("hold".parse::<OrderSide>().is_err());
+    }
+}
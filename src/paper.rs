@@ -0,0 +1,160 @@
+//! Paper trading.
+//!
+//! With `PAPER_TRADING` set, the portfolio swaps against a [`PaperWallet`]
+//! instead of Jupiter: fills are computed from market prices with a
+//! [`FillModel`], balances are simulated, and every map holding trading state
+//! lives under a separate `paper_` namespace (see [`trading_prefix`]). The
+//! wallet is never touched, the JIMMY token is never launched and Twitter is
+//! mocked, so the pipeline can run for weeks on paper.
+use anyhow::Result;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use std::sync::OnceLock;
+
+use crate::backtest::FillModel;
+use crate::config::Config;
+use crate::constant::*;
+use crate::fill::SwapFill;
+use crate::store::{LocalStore, Store, StoreMap};
+
+/// Prefix of a map holding trading state, kept apart in paper trading.
+pub fn trading_prefix(prefix: &str) -> String {
+    if Config::get().paper_trading {
+        format!("paper_{}", prefix)
+    } else {
+        prefix.to_string()
+    }
+}
+
+/// Simulated balances of the paper trading mode, in raw amounts.
+pub struct PaperWallet {
+    fill_model: FillModel,
+    balances: StoreMap<String, u64, LocalStore>,
+}
+
+impl PaperWallet {
+    const PAPER_WALLET_PREFIX: &'static str = "paper_wallet";
+    const SOL_KEY: &'static str = "sol";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<PaperWallet> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        let config = Config::get();
+        Self {
+            fill_model: FillModel::new(config.paper_fee_lamports, config.paper_slippage_bps),
+            balances: LocalStore::open_map(Self::PAPER_WALLET_PREFIX),
+        }
+    }
+
+    pub fn sol_balance(&self) -> Result<u64> {
+        match self.balances.get(&Self::SOL_KEY.to_string())? {
+            Some(balance) => Ok(balance),
+            None => {
+                let balance = (Config::get().paper_initial_sol * LAMPORTS_PER_SOL as f64) as u64;
+                self.balances.insert(Self::SOL_KEY.to_string(), balance)?;
+                Ok(balance)
+            }
+        }
+    }
+
+    /// Raw balance of a token, `initial` raw tokens on first use.
+    pub fn token_balance(&self, mint: &Pubkey, initial: u64) -> Result<u64> {
+        match self.balances.get(&mint.to_string())? {
+            Some(balance) => Ok(balance),
+            None => {
+                self.balances.insert(mint.to_string(), initial)?;
+                Ok(initial)
+            }
+        }
+    }
+
+    /// Simulates spending `sol_amount` lamports on a token priced at
+    /// `price_in_sol` SOL per UI unit, quoted at the price without costs.
+    pub fn buy(
+        &self,
+        mint: &Pubkey,
+        sol_amount: u64,
+        price_in_sol: f64,
+        decimals: u8,
+    ) -> Result<(SwapFill, Signature)> {
+        let sol_balance = self.sol_balance()?;
+        if sol_balance < sol_amount {
+            return Err(anyhow::anyhow!("Not enough paper SOL to buy"));
+        }
+
+        let token_amount = self.fill_model.buy(sol_amount, price_in_sol, decimals);
+        let token_balance = self.token_balance(mint, 0)?;
+        self.balances
+            .insert(Self::SOL_KEY.to_string(), sol_balance - sol_amount)?;
+        self.balances
+            .insert(mint.to_string(), token_balance + token_amount)?;
+
+        tracing::info!(
+            "Paper buy: {} lamports for {} of {} at {} SOL",
+            sol_amount,
+            token_amount,
+            mint,
+            price_in_sol
+        );
+        let fill = SwapFill {
+            fee_lamports: self.fill_model.fee_lamports,
+            net_sol: sol_amount,
+            token_amount,
+            quoted_out: FillModel::new(0, 0).buy(sol_amount, price_in_sol, decimals),
+            actual_out: token_amount,
+        };
+        Ok((fill, Signature::new_unique()))
+    }
+
+    /// Simulates selling `token_amount` raw tokens priced at `price_in_sol`
+    /// SOL per UI unit, quoted at the price without costs.
+    pub fn sell(
+        &self,
+        mint: &Pubkey,
+        token_amount: u64,
+        price_in_sol: f64,
+        decimals: u8,
+    ) -> Result<(SwapFill, Signature)> {
+        let token_balance = self.token_balance(mint, 0)?;
+        if token_balance < token_amount {
+            return Err(anyhow::anyhow!(
+                "Not enough paper tokens of {} to sell",
+                mint
+            ));
+        }
+
+        let sol_amount = self.fill_model.sell(token_amount, price_in_sol, decimals);
+        let sol_balance = self.sol_balance()?;
+        self.balances
+            .insert(mint.to_string(), token_balance - token_amount)?;
+        self.balances
+            .insert(Self::SOL_KEY.to_string(), sol_balance + sol_amount)?;
+
+        tracing::info!(
+            "Paper sell: {} of {} for {} lamports at {} SOL",
+            token_amount,
+            mint,
+            sol_amount,
+            price_in_sol
+        );
+        let fill = SwapFill {
+            fee_lamports: self.fill_model.fee_lamports,
+            net_sol: sol_amount,
+            token_amount,
+            quoted_out: FillModel::new(0, 0).sell(token_amount, price_in_sol, decimals),
+            actual_out: sol_amount + self.fill_model.fee_lamports,
+        };
+        Ok((fill, Signature::new_unique()))
+    }
+
+    pub fn burn(&self, mint: &Pubkey, token_amount: u64) -> Result<Signature> {
+        let token_balance = self.token_balance(mint, 0)?;
+        self.balances
+            .insert(mint.to_string(), token_balance.saturating_sub(token_amount))?;
+
+        Ok(Signature::new_unique())
+    }
+}
@@ -1,21 +1,33 @@
 use anyhow::Result;
+use solana_sdk::hash::hash;
 
 use std::collections::HashSet;
+use std::future::Future;
 use std::time::Duration;
 
 use crate::actions::portfolio::PortfolioAction;
 use crate::actions::twitter::TwitterAction;
 use crate::actions::utils::get_cur_timestamp;
 use crate::actions::Action;
+use crate::buyback::Buyback;
 use crate::config::Config;
 use crate::constant::*;
+use crate::exit::ExitMonitor;
 use crate::feed::{Feed, FeedType};
 use crate::llm::azure::run_prompt;
 use crate::llm::scorer::score_reply;
+use crate::nav::NavHistory;
+use crate::orders::LimitOrders;
 use crate::portfolio::Portfolio;
-use crate::price::coingecko::CoinGeckoProvider;
-use crate::strategy::select_tokens;
+use crate::price::{PriceAggregator, PriceId, PriceProvider};
+use crate::rebalance::{Order, Rebalancer};
+use crate::reconcile::reconcile_holdings;
+use crate::risk::RiskManager;
+use crate::round::{FeedInput, PriceRecord, RoundRecord, RoundRecords};
+use crate::strategy::{select_tokens, Trade};
+use crate::swap::sender::SendError;
 use crate::token::jimmy::JimmyToken;
+use crate::twap::TwapScheduler;
 use crate::twitter::{Reply, TweetType, TwitterClient, TwitterPrompt};
 
 pub struct Pipeline {
@@ -30,11 +42,32 @@ impl Pipeline {
     pub async fn run_once(&self, sell_jimmy: bool) -> Result<()> {
         tracing::info!("Running pipeline once");
 
+        // Record the round whether it succeeds or not
+        let mut record = RoundRecord::start()?;
+        let result = self.run_round(sell_jimmy, &mut record).await;
+        record.finish(result.as_ref().err());
+        let slippage = record.slippage();
+        if slippage.swaps > 0 {
+            tracing::info!("Round {} fills: {}", record.id, slippage);
+        }
+        if let Err(e) = RoundRecords::get().insert(&record) {
+            tracing::error!("Failed to record round {}: {}", record.id, e);
+        }
+        result?;
+
+        // handle investor memo
+        self.handle_investor_memo().await?;
+
+        Ok(())
+    }
+
+    async fn run_round(&self, sell_jimmy: bool, record: &mut RoundRecord) -> Result<()> {
         let mut candidates: HashSet<String> = HashSet::new();
         for feed in self.feeds.iter() {
             match feed.feed_type() {
                 FeedType::Newsletter => {
                     if let Some(content) = feed.fetch().await? {
+                        let content_hash = hash(content.as_bytes()).to_string();
                         let prompt = feed.construct_prompt(content);
                         let response = run_prompt(prompt).await?;
                         let tokens: Vec<String> = serde_json::from_str::<Vec<String>>(&response)?
@@ -43,13 +76,32 @@ impl Pipeline {
                             .collect();
                         tracing::info!("Recommended tokens in newsletter: {}", tokens.join(", "));
 
+                        record.feeds.push(FeedInput {
+                            feed_type: feed.feed_type(),
+                            content_hash,
+                            llm_output: response,
+                            tokens: tokens.clone(),
+                        });
                         candidates.extend(tokens);
                     }
                 }
             }
         }
 
-        let trades = select_tokens(candidates).await?;
+        record.candidates = candidates.iter().cloned().collect();
+        let selection = select_tokens(candidates).await?;
+        record.performances = selection.performances;
+        record.rejections = selection.rejections;
+        record.trades = selection.trades.clone();
+        for (token, price) in selection.prices {
+            record.prices.push(PriceRecord {
+                symbol: token.symbol,
+                address: token.address.to_string(),
+                price,
+                currency: USD_CURRENCY.to_string(),
+            });
+        }
+        let trades = selection.trades;
 
         let portfolio = Portfolio::get().await;
 
@@ -72,18 +124,15 @@ impl Pipeline {
             }
 
             let profit = {
-                let price_provider = CoinGeckoProvider::get();
-                let prices =
-                    if let Some(coingecko_id) = token_holding.token_info.coingecko_id.as_ref() {
-                        price_provider
-                            .get_prices_by_ids(&[&coingecko_id, SOL_COINGECKO_ID], USD_CURRENCY)
-                            .await?
-                    } else {
-                        return Err(anyhow::anyhow!("No coingecko id found"));
-                    };
-
-                let token_price = prices[0];
-                let sol_price = prices[1];
+                let ids = [PriceId::from(&token_holding.token_info), PriceId::sol()];
+                let prices = PriceAggregator::get().prices(&ids, USD_CURRENCY).await?;
+                let (Some(token_price), Some(sol_price)) = (prices[0], prices[1]) else {
+                    return Err(anyhow::anyhow!(
+                        "No price found for {}",
+                        token_holding.token_info.symbol
+                    ));
+                };
+
                 token_holding.profit_margin_from_usd(sol_price, token_price)
             };
 
@@ -102,23 +151,79 @@ impl Pipeline {
             portfolio.sell_jimmy(sell_jimmy_amount as u64).await?;
         }
 
-        // Buy tokens
+        // Rebalance the selected holdings toward the trade weights, selling
+        // overweight holdings first
+        let valuation = portfolio.valuation().await?;
+        for holding in &valuation.holdings {
+            record.prices.push(PriceRecord {
+                symbol: holding.holding.token_info.symbol.clone(),
+                address: holding.holding.token_info.address.to_string(),
+                price: holding.price_in_sol,
+                currency: SOL_CURRENCY.to_string(),
+            });
+        }
+        let orders = Rebalancer::from_config(config).plan(&trades, amount_to_buy, &valuation);
+        // The buys are bounded by the budget plus the sell proceeds, weigh
+        // them against their total
+        let buy_budget: f64 = orders
+            .iter()
+            .map(|order| match order {
+                Order::Buy { sol_amount, .. } => *sol_amount as f64,
+                Order::Sell { .. } => 0.0,
+            })
+            .sum();
+        let mut buys = vec![];
+        for order in orders {
+            tracing::info!("Rebalance order: {}", order);
+            match order {
+                Order::Sell { token, amount } => {
+                    if let Err(e) = portfolio.sell_token(&token, amount).await {
+                        tracing::error!("Failed to sell token: {}", e);
+                    }
+                }
+                Order::Buy { token, sol_amount } => buys.push(Trade {
+                    token,
+                    weight: sol_amount as f64 / buy_budget,
+                }),
+            }
+        }
+
+        // Veto or resize buys breaking the risk limits
+        let valuation = portfolio.valuation().await?;
+        let trades = RiskManager::get().review(buys, buy_budget, &valuation)?;
+
+        // Buy tokens, in slices over time when large enough
+        let twap = TwapScheduler::get();
         for trade in trades {
-            let sol_amount = (amount_to_buy * trade.weight).floor() as u64;
+            let sol_amount = (buy_budget * trade.weight).floor() as u64;
+            let slices = twap.plan().slice_count(sol_amount);
+            if slices > 1 {
+                if let Err(e) = twap
+                    .schedule(&trade.token, sol_amount, slices, record.id)
+                    .await
+                {
+                    tracing::error!("Failed to schedule TWAP order: {}", e);
+                }
+                continue;
+            }
+
             if let Err(e) = portfolio.buy_token(&trade.token, sol_amount).await {
                 tracing::error!("Failed to buy token: {}", e);
+                // The next buys would fail the same way
+                if e.downcast_ref::<SendError>() == Some(&SendError::InsufficientFunds) {
+                    break;
+                }
             }
         }
 
-        // handle investor memo
-        self.handle_investor_memo().await?;
-
         Ok(())
     }
 
     pub async fn handle_investor_memo(&self) -> anyhow::Result<()> {
         const ACTIVE_TIME: u64 = 60 * 60 * 24; // 1 day
-        let last_time = match TwitterAction::iter().next() {
+        let last_memo = TwitterAction::iter()
+            .find(|(action, _)| matches!(action, TwitterAction::InvestorMemo { .. }));
+        let last_time = match last_memo {
             Some((_, raw)) => {
                 if raw.timestamp() + ACTIVE_TIME > get_cur_timestamp() {
                     return Ok(());
@@ -232,6 +337,27 @@ impl Pipeline {
         let twitter_interval = Duration::from_secs(60 * 5);
         let mut twitter_timer = tokio::time::interval(twitter_interval);
 
+        // Stops are checked on their own task, never waiting for a round
+        let exit_interval = Duration::from_secs(Config::get().exit_monitor_interval_secs);
+        spawn_every("check exit rules", exit_interval, || {
+            ExitMonitor::get().check()
+        });
+
+        // Snapshots keep their pace through the rounds, leaving no gaps
+        let nav_interval = Duration::from_secs(Config::get().nav_snapshot_interval_secs);
+        spawn_every("snapshot NAV", nav_interval, || async {
+            NavHistory::get().record().await.map(|_| ())
+        });
+
+        // Orders are watched through the rounds, the slices sent on time
+        let order_interval = Duration::from_secs(Config::get().order_monitor_interval_secs);
+        spawn_every("check limit orders", order_interval, || {
+            LimitOrders::get().check()
+        });
+        spawn_every("check TWAP orders", order_interval, || {
+            TwapScheduler::get().check()
+        });
+
         loop {
             tokio::select! {
                 _ = trading_timer.tick() => {
@@ -241,6 +367,14 @@ impl Pipeline {
                         tracing::error!("Failed to run trading pipeline: {}", e);
                     }
 
+                    if let Err(e) = reconcile_holdings().await {
+                        tracing::error!("Failed to reconcile holdings: {}", e);
+                    }
+
+                    if let Err(e) = Buyback::get().run_if_due().await {
+                        tracing::error!("Failed to run buyback: {}", e);
+                    }
+
                     tracing::info!("Trading round {} completed", trading_round);
                     trading_round += 1;
                 }
@@ -272,3 +406,21 @@ impl PipelineBuilder {
         self
     }
 }
+
+/// Runs `check` every `interval` on a task of its own, so a long trading
+/// round never delays it.
+fn spawn_every<F, Fut>(name: &'static str, interval: Duration, check: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    tokio::spawn(async move {
+        let mut timer = tokio::time::interval(interval);
+        loop {
+            timer.tick().await;
+            if let Err(e) = check().await {
+                tracing::error!("Failed to {}: {}", name, e);
+            }
+        }
+    });
+}
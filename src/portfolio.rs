@@ -1,20 +1,41 @@
 use serde::{Deserialize, Serialize};
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
 use tokio::sync::OnceCell;
 use tracing::instrument;
 
+use std::collections::HashMap;
+use std::time::Duration;
+
 use crate::{
-    actions::{portfolio::PortfolioAction, Action},
-    jupiter::swap::{swap_from_sol, swap_to_sol},
+    actions::{portfolio::PortfolioAction, utils::get_cur_timestamp, Action},
+    buyback::Buyback,
+    config::Config,
+    constant::{FILL_MAX_RETRIES, FILL_RETRY_DELAY_SECS, SOL_CURRENCY, USD_CURRENCY},
+    cost_basis::CostBasis,
+    fill::SwapFill,
+    indicators::{closes, realized_volatility},
+    journal::{IntentKind, IntentState, TradeIntent, TradeJournal},
+    paper::{trading_prefix, PaperWallet},
+    price::{PriceAggregator, PriceId, PriceProvider},
     store::{map::StoreMap, LocalStore, Store},
+    swap::{
+        guard::QuoteGuard,
+        params::{SlippagePolicy, SwapSettings},
+        raydium::pool_price,
+        sender::SendError,
+        SwapExecutor,
+    },
     token::{jimmy::JimmyToken, structs::TokenInfo},
+    twap::TwapScheduler,
     wallet::Wallet,
     LAMPORTS_PER_SOL,
 };
 
 pub struct Portfolio {
     jimmy_token: JimmyHolding,
-    tokens: StoreMap<String, OtherTokenHolding, LocalStore>,
+    executor: Box<dyn SwapExecutor>,
+    /// Holdings by mint
+    tokens: StoreMap<Pubkey, OtherTokenHolding, LocalStore>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,31 +96,14 @@ impl OtherTokenHolding {
     }
 
     #[instrument(name = "Holding::UpdateSell", skip(self))]
-    pub fn update_sell(&mut self, sol_earned: u64, token_decrease: u64) {
+    pub fn update_sell(&mut self, sol_earned: u64, token_decrease: u64, cost_basis: CostBasis) {
         tracing::debug!("update sell");
 
-        let mut remaining = token_decrease;
-        let mut cost = 0.0;
-
-        while let Some(shot) = self.shots.pop() {
-            let cut = remaining.min(shot.quantity);
-            remaining -= cut;
-            cost += shot.avg_cost() * cut as f64;
-
-            if remaining == 0 {
-                if cut < shot.quantity {
-                    let total_cost_in_sol = shot.avg_cost() * (shot.quantity - cut) as f64;
-                    let new_shot = OneShot {
-                        quantity: shot.quantity - cut,
-                        total_cost_in_sol,
-                    };
-                    self.shots.push(new_shot);
-                }
-                break;
-            }
-        }
+        let taken = cost_basis.take(&mut self.shots, token_decrease);
+        let quantity: u64 = taken.iter().map(|(_, shot)| shot.quantity).sum();
+        let cost: f64 = taken.iter().map(|(_, shot)| shot.total_cost_in_sol).sum();
 
-        assert_eq!(remaining, 0);
+        assert_eq!(quantity, token_decrease);
 
         self.total_pnl += sol_earned as f64 - cost;
     }
@@ -121,8 +125,67 @@ impl OtherTokenHolding {
     }
 }
 
+/// A token holding valued at the current market price.
+#[derive(Debug, Clone)]
+pub struct HoldingValuation {
+    pub holding: OtherTokenHolding,
+    /// Price of one UI unit of the token in SOL
+    pub price_in_sol: f64,
+}
+
+impl HoldingValuation {
+    /// Value of the holding in lamports
+    pub fn value(&self) -> f64 {
+        self.holding.holding_ui_amount() * self.price_in_sol * LAMPORTS_PER_SOL as f64
+    }
+}
+
+/// Snapshot of the SOL balance and the token holdings valued in SOL.
+#[derive(Debug, Clone)]
+pub struct PortfolioValuation {
+    pub sol_balance: u64,
+    pub holdings: Vec<HoldingValuation>,
+    pub sol_price_usd: f64,
+    /// Lamports of the pending TWAP slices by mint, still in the SOL balance
+    /// but committed to their buys
+    pub committed: HashMap<Pubkey, u64>,
+}
+
+impl PortfolioValuation {
+    /// Value of the token holdings in lamports
+    pub fn holdings_value(&self) -> f64 {
+        self.holdings.iter().map(|h| h.value()).sum()
+    }
+
+    /// Net asset value in lamports, JIMMY inventory excluded
+    pub fn nav(&self) -> f64 {
+        self.sol_balance as f64 + self.holdings_value()
+    }
+
+    pub fn holding(&self, token_info: &TokenInfo) -> Option<&HoldingValuation> {
+        self.holdings
+            .iter()
+            .find(|h| h.holding.token_info == *token_info)
+    }
+
+    /// Lamports committed to the pending buys of the token
+    pub fn committed(&self, token_info: &TokenInfo) -> f64 {
+        self.committed
+            .get(&token_info.address)
+            .copied()
+            .unwrap_or_default() as f64
+    }
+
+    /// Lamports committed to every pending buy
+    pub fn committed_value(&self) -> f64 {
+        self.committed.values().sum::<u64>() as f64
+    }
+}
+
 impl Portfolio {
-    const HOLDING_TOKENS_PREFIX: &'static str = "holding_tokens";
+    const HOLDING_TOKENS_PREFIX: &'static str = "token_holdings";
+    /// Holdings keyed by symbol, migrated to the mint keys
+    const LEGACY_HOLDING_TOKENS_PREFIX: &'static str = "holding_tokens";
 
     pub async fn get() -> &'static Portfolio {
         static PORTFOLIO: OnceCell<Portfolio> = OnceCell::const_new();
@@ -140,16 +203,55 @@ impl Portfolio {
 
         let jimmy_holding = JimmyHolding {
             mint: jimmy_token.mint_pubkey(),
+            pool_id: jimmy_token.pool_id(),
         };
 
-        Ok(Self {
-            jimmy_token: jimmy_holding,
-            tokens: LocalStore::open_map(Self::HOLDING_TOKENS_PREFIX),
-        })
+        let executor = Config::get().swap_backend.executor();
+        tracing::info!("Swapping with {}", executor.name());
+
+        Self::with_executor(jimmy_holding, executor)
+    }
+
+    /// A portfolio of the JIMMY token `jimmy_token` swapping through
+    /// `executor`, such as a [`crate::swap::mock::MockAmm`] in tests.
+    pub fn with_executor(
+        jimmy_token: JimmyHolding,
+        executor: Box<dyn SwapExecutor>,
+    ) -> anyhow::Result<Self> {
+        let portfolio = Self {
+            jimmy_token,
+            executor,
+            tokens: LocalStore::open_map(trading_prefix(Self::HOLDING_TOKENS_PREFIX)),
+        };
+        portfolio.migrate_symbol_keys()?;
+
+        Ok(portfolio)
+    }
+
+    /// Moves the holdings keyed by symbol under their mint.
+    fn migrate_symbol_keys(&self) -> anyhow::Result<()> {
+        let legacy: StoreMap<String, OtherTokenHolding, LocalStore> =
+            LocalStore::open_map(trading_prefix(Self::LEGACY_HOLDING_TOKENS_PREFIX));
+        legacy.drain_into(|symbol, holding| {
+            tracing::info!(
+                "Migrating holding {} to mint {}",
+                symbol,
+                holding.token_info.address
+            );
+            self.tokens.insert(holding.token_info.address, holding)
+        })?;
+
+        Ok(())
     }
 
     pub async fn jimmy_balance(&self) -> anyhow::Result<u64> {
         let jimmy_mint = self.jimmy_token.mint;
+        let config = Config::get();
+        if config.paper_trading {
+            let initial = config.paper_initial_jimmy * JimmyToken::one_jimmy();
+            return PaperWallet::get().token_balance(&jimmy_mint, initial);
+        }
+
         let wallet = Wallet::get();
         let balance = wallet.get_token_balance(&jimmy_mint).await?;
 
@@ -157,35 +259,423 @@ impl Portfolio {
     }
 
     pub async fn sol_balance(&self) -> anyhow::Result<u64> {
+        if Config::get().paper_trading {
+            return PaperWallet::get().sol_balance();
+        }
+
         let wallet = Wallet::get();
         let balance = wallet.balance()?;
 
         Ok(balance)
     }
 
-    pub fn tokens(&self) -> &StoreMap<String, OtherTokenHolding, LocalStore> {
+    pub fn tokens(&self) -> &StoreMap<Pubkey, OtherTokenHolding, LocalStore> {
         &self.tokens
     }
 
+    /// Price of one UI unit of a token in SOL, JIMMY at the current pool
+    /// price.
+    pub async fn price_in_sol(
+        &self,
+        mint: &Pubkey,
+        coingecko_id: Option<&str>,
+    ) -> anyhow::Result<f64> {
+        if *mint == self.jimmy_token.mint {
+            return self.jimmy_price_in_sol().await;
+        }
+
+        let ids = [PriceId::from_mint(mint, coingecko_id), PriceId::sol()];
+        let prices = PriceAggregator::get().prices(&ids, USD_CURRENCY).await?;
+        match (prices[0], prices[1]) {
+            (Some(price), Some(sol_price)) => Ok(price / sol_price),
+            _ => Err(anyhow::anyhow!("No price found for {}", mint)),
+        }
+    }
+
+    /// Price of one JIMMY in SOL in its pool, the launch price on paper
+    /// without a pool.
+    pub async fn jimmy_price_in_sol(&self) -> anyhow::Result<f64> {
+        match self.jimmy_token.pool_id {
+            Some(pool_id) => pool_price(&pool_id, &self.jimmy_token.mint).await,
+            None if Config::get().paper_trading => Ok(Config::get().raydium_pool_price),
+            None => Err(anyhow::anyhow!("No pool to price JIMMY")),
+        }
+    }
+
+    /// Swaps `sol_amount` lamports to the token, on paper in paper trading.
+    async fn swap_from_sol(
+        &self,
+        mint: &Pubkey,
+        decimals: u8,
+        coingecko_id: Option<&str>,
+        sol_amount: u64,
+        intent: Option<&mut TradeIntent>,
+    ) -> anyhow::Result<SwapOutcome> {
+        if Config::get().paper_trading {
+            let price_in_sol = self.price_in_sol(mint, coingecko_id).await?;
+            let (fill, sig) = PaperWallet::get().buy(mint, sol_amount, price_in_sol, decimals)?;
+            if let Some(intent) = intent {
+                TradeJournal::get().submit(intent, &sig, fill.token_amount)?;
+            }
+            return Ok(SwapOutcome {
+                out_amount: fill.token_amount,
+                sig,
+                fill: Some(fill),
+                settings: None,
+            });
+        }
+
+        let sol_mint = spl_token::native_mint::id();
+        self.execute_swap(&sol_mint, mint, decimals, coingecko_id, sol_amount, intent)
+            .await
+    }
+
+    /// Swaps `token_amount` raw tokens to SOL, on paper in paper trading.
+    async fn swap_to_sol(
+        &self,
+        mint: &Pubkey,
+        decimals: u8,
+        coingecko_id: Option<&str>,
+        token_amount: u64,
+        intent: Option<&mut TradeIntent>,
+    ) -> anyhow::Result<SwapOutcome> {
+        if Config::get().paper_trading {
+            let price_in_sol = self.price_in_sol(mint, coingecko_id).await?;
+            let (fill, sig) =
+                PaperWallet::get().sell(mint, token_amount, price_in_sol, decimals)?;
+            if let Some(intent) = intent {
+                TradeJournal::get().submit(intent, &sig, fill.net_sol)?;
+            }
+            return Ok(SwapOutcome {
+                out_amount: fill.net_sol,
+                sig,
+                fill: Some(fill),
+                settings: None,
+            });
+        }
+
+        let sol_mint = spl_token::native_mint::id();
+        self.execute_swap(
+            mint,
+            &sol_mint,
+            decimals,
+            coingecko_id,
+            token_amount,
+            intent,
+        )
+        .await
+    }
+
+    /// Swaps with the executor a token of `decimals` against SOL, checking
+    /// the quote and journaling the signature in `intent` before sending, and
+    /// quoting again when the blockhash expires before the swap lands.
+    async fn execute_swap(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        decimals: u8,
+        coingecko_id: Option<&str>,
+        amount: u64,
+        mut intent: Option<&mut TradeIntent>,
+    ) -> anyhow::Result<SwapOutcome> {
+        let (mint, kind) = if *input_mint == spl_token::native_mint::id() {
+            (output_mint, IntentKind::Buy)
+        } else {
+            (input_mint, IntentKind::Sell)
+        };
+        let reference_price = match self.price_in_sol(mint, coingecko_id).await {
+            Ok(price) => Some(price),
+            Err(e) => {
+                tracing::warn!("No reference price of {} to check the quote: {}", mint, e);
+                None
+            }
+        };
+
+        let trade_sol = match kind {
+            IntentKind::Buy => Some(amount as f64 / LAMPORTS_PER_SOL as f64),
+            IntentKind::Sell => {
+                reference_price.map(|price| amount as f64 / 10_f64.powi(decimals as i32) * price)
+            }
+        };
+        let slippage_bps = self
+            .slippage_bps(&PriceId::from_mint(mint, coingecko_id), trade_sol)
+            .await;
+        let guard = QuoteGuard::from_config(Config::get());
+        let max_requotes = Config::get().swap_max_requotes;
+        let mut requotes = 0;
+        loop {
+            let prepared = self
+                .executor
+                .prepare(input_mint, output_mint, amount, slippage_bps)
+                .await?;
+
+            if let Err(reason) = guard.check(&prepared, decimals, reference_price) {
+                tracing::warn!(
+                    "Rejected quote of {} on {}: {}",
+                    mint,
+                    self.executor.name(),
+                    reason
+                );
+                return Err(anyhow::anyhow!("Quote of {} rejected: {}", mint, reason));
+            }
+
+            // Simulated before it is journaled, so a swap rejected here was
+            // never broadcast
+            let simulated = self.executor.simulate(&prepared).await;
+            let broadcast = simulated.is_ok();
+            let sent = match simulated {
+                Ok(()) => {
+                    // A re-quote replaces the expired transaction in the journal
+                    if let Some(intent) = intent.as_deref_mut() {
+                        TradeJournal::get().submit(
+                            intent,
+                            &prepared.signature(),
+                            prepared.out_amount,
+                        )?;
+                    }
+                    self.executor.send(&prepared).await
+                }
+                Err(e) => Err(e),
+            };
+            let sig = match sent {
+                Ok(sig) => sig,
+                Err(e) if requotes < max_requotes && is_expired(&e) => {
+                    requotes += 1;
+                    tracing::warn!(
+                        "Swap of {} expired, re-quoting ({}/{})",
+                        mint,
+                        requotes,
+                        max_requotes
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    // Never broadcast or expired unseen, it can no longer
+                    // land, so its intent fails without waiting on chain
+                    if !broadcast || is_expired(&e) {
+                        if let Some(intent) = intent.as_deref_mut() {
+                            TradeJournal::get().fail(intent, &e)?;
+                        }
+                    }
+                    return Err(e);
+                }
+            };
+
+            let fill = self.read_fill(&sig, mint, kind, prepared.out_amount).await;
+            let outcome = SwapOutcome::new(kind, prepared.out_amount, sig, fill);
+            return Ok(SwapOutcome {
+                settings: Some(prepared.settings),
+                ..outcome
+            });
+        }
+    }
+
+    /// Slippage tolerance of a swap of `trade_sol` SOL worth of a token, from
+    /// its volatility and volume of the last day.
+    async fn slippage_bps(&self, id: &PriceId, trade_sol: Option<f64>) -> u64 {
+        let policy = SlippagePolicy::from_config(Config::get());
+        let history = PriceAggregator::get().history(id, SOL_CURRENCY, 1).await;
+        match history {
+            Ok(history) => {
+                let volatility = realized_volatility(&closes(&history.prices));
+                let participation = history
+                    .total_volumes
+                    .last()
+                    .map(|volume| volume[1])
+                    .filter(|volume| *volume > 0.0)
+                    .zip(trade_sol)
+                    .map(|(volume, trade_sol)| trade_sol / volume);
+                policy.slippage_bps(Some(volatility), participation)
+            }
+            Err(e) => {
+                tracing::warn!("No market data of {} to size the slippage: {}", id.mint, e);
+                policy.slippage_bps(None, None)
+            }
+        }
+    }
+
+    /// Reads the fill of a landed swap, retrying while the transaction is not
+    /// indexed yet, or `None` to fall back on the quote.
+    async fn read_fill(
+        &self,
+        sig: &Signature,
+        mint: &Pubkey,
+        kind: IntentKind,
+        quoted_out: u64,
+    ) -> Option<SwapFill> {
+        // The transaction may be confirmed before the RPC node serves it
+        for retry in 1..=FILL_MAX_RETRIES {
+            match self.executor.fill(sig, mint, kind, quoted_out).await {
+                // A swap without token changes was read from incomplete balances
+                Ok(fill) if fill.token_amount > 0 => return Some(fill),
+                Ok(_) => tracing::warn!("No token balance change found in {}", sig),
+                Err(e) => tracing::warn!(
+                    "Failed to read the fill of {} ({}/{}): {}",
+                    sig,
+                    retry,
+                    FILL_MAX_RETRIES,
+                    e
+                ),
+            }
+            if retry < FILL_MAX_RETRIES {
+                tokio::time::sleep(Duration::from_secs(FILL_RETRY_DELAY_SECS)).await;
+            }
+        }
+        None
+    }
+
+    /// Values the SOL balance and every open holding with the aggregated prices.
+    pub async fn valuation(&self) -> anyhow::Result<PortfolioValuation> {
+        let holdings = self
+            .tokens()
+            .iter()
+            .map(|(_, holding)| holding.into_owned())
+            .filter(|holding| holding.holding_amount() > 0)
+            .collect::<Vec<_>>();
+
+        let mut ids = vec![PriceId::sol()];
+        ids.extend(
+            holdings
+                .iter()
+                .map(|holding| PriceId::from(&holding.token_info)),
+        );
+        let prices = PriceAggregator::get().prices(&ids, USD_CURRENCY).await?;
+        let sol_price_usd = prices[0].ok_or(anyhow::anyhow!("No price found for SOL"))?;
+
+        let holdings = holdings
+            .into_iter()
+            .zip(prices.into_iter().skip(1))
+            .map(|(holding, price_usd)| {
+                let price_in_sol = match price_usd {
+                    Some(price_usd) => price_usd / sol_price_usd,
+                    None => {
+                        tracing::warn!(
+                            "No price found for {}, valued at 0",
+                            holding.token_info.symbol
+                        );
+                        0.0
+                    }
+                };
+                HoldingValuation {
+                    holding,
+                    price_in_sol,
+                }
+            })
+            .collect();
+
+        Ok(PortfolioValuation {
+            sol_balance: self.sol_balance().await?,
+            holdings,
+            sol_price_usd,
+            committed: TwapScheduler::get().committed(),
+        })
+    }
+
     pub async fn sell_token(
         &self,
         token_info: &TokenInfo,
         token_amount: u64,
     ) -> anyhow::Result<()> {
-        let mut token_holding = self
+        let token_holding = self
             .tokens()
-            .get(&token_info.symbol)?
+            .get(&token_info.address)?
             .ok_or(anyhow::anyhow!(
-                "Token Holding {} not found",
-                token_info.symbol
+                "Token Holding {} ({}) not found",
+                token_info.symbol,
+                token_info.address
             ))?;
-        let old_pnl = token_holding.total_pnl;
         if token_holding.holding_amount() < token_amount {
             return Err(anyhow::anyhow!("Not enough tokens to sell"));
         }
 
-        let (sol_amount, sig) = swap_to_sol(&token_info.address.to_string(), token_amount).await?;
-        token_holding.update_sell(sol_amount, token_amount);
+        let journal = TradeJournal::get();
+        let mut intent = journal.open(IntentKind::Sell, token_info, token_amount)?;
+        let swapped = self
+            .swap_to_sol(
+                &token_info.address,
+                token_info.decimals,
+                token_info.coingecko_id.as_deref(),
+                token_amount,
+                Some(&mut intent),
+            )
+            .await;
+        let outcome = match swapped {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                self.settle_failed_swap(&mut intent).await;
+                return Err(e);
+            }
+        };
+
+        self.apply_sell(token_info, token_amount, &outcome)?;
+        journal.complete(&mut intent, &outcome.sig, outcome.out_amount)
+    }
+
+    /// Buys the token with `sol_amount` lamports, returning the signature of
+    /// the swap.
+    pub async fn buy_token(
+        &self,
+        token_info: &TokenInfo,
+        sol_amount: u64,
+    ) -> anyhow::Result<Signature> {
+        let sol_balance = self.sol_balance().await?;
+        if sol_balance < sol_amount {
+            return Err(anyhow::anyhow!("Not enough SOL to buy"));
+        }
+
+        self.journaled_buy(token_info, sol_amount).await
+    }
+
+    /// Buys a token through the journal, the SOL balance checked already.
+    async fn journaled_buy(
+        &self,
+        token_info: &TokenInfo,
+        sol_amount: u64,
+    ) -> anyhow::Result<Signature> {
+        let journal = TradeJournal::get();
+        let mut intent = journal.open(IntentKind::Buy, token_info, sol_amount)?;
+        let swapped = self
+            .swap_from_sol(
+                &token_info.address,
+                token_info.decimals,
+                token_info.coingecko_id.as_deref(),
+                sol_amount,
+                Some(&mut intent),
+            )
+            .await;
+        let outcome = match swapped {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                self.settle_failed_swap(&mut intent).await;
+                return Err(e);
+            }
+        };
+
+        self.apply_buy(token_info, sol_amount, &outcome)?;
+        journal.complete(&mut intent, &outcome.sig, outcome.out_amount)?;
+        Ok(outcome.sig)
+    }
+
+    /// Updates the holding and logs a sell that landed on chain, realizing
+    /// the PnL net of the fees when the fill is known.
+    fn apply_sell(
+        &self,
+        token_info: &TokenInfo,
+        token_amount: u64,
+        outcome: &SwapOutcome,
+    ) -> anyhow::Result<()> {
+        let mut token_holding = self
+            .tokens()
+            .get(&token_info.address)?
+            .ok_or(anyhow::anyhow!(
+                "Token Holding {} ({}) not found",
+                token_info.symbol,
+                token_info.address
+            ))?;
+        log_fill(token_info, outcome);
+        let token_amount = outcome.in_amount(IntentKind::Sell, token_amount);
+        let old_pnl = token_holding.total_pnl;
+        token_holding.update_sell(outcome.out_amount, token_amount, Config::get().cost_basis);
         let this_pnl = token_holding.total_pnl - old_pnl;
 
         // action log
@@ -193,9 +683,11 @@ impl Portfolio {
             let sell_action = PortfolioAction::sell(
                 token_info.address.to_string(),
                 token_amount,
-                sol_amount,
-                sig.to_string(),
-            );
+                outcome.out_amount,
+                outcome.sig.to_string(),
+            )
+            .with_fill(outcome.fill)
+            .with_settings(outcome.settings);
             let pnl_action = PortfolioAction::pnl(
                 token_info.address.to_string(),
                 this_pnl,
@@ -205,91 +697,229 @@ impl Portfolio {
             pnl_action.log();
         }
 
-        self.tokens()
-            .insert(token_info.symbol.clone(), token_holding)?;
+        self.tokens().insert(token_info.address, token_holding)?;
         Ok(())
     }
 
-    pub async fn buy_token(&self, token_info: &TokenInfo, sol_amount: u64) -> anyhow::Result<()> {
-        let sol_balance = self.sol_balance().await?;
-        if sol_balance < sol_amount {
-            return Err(anyhow::anyhow!("Not enough SOL to buy"));
-        }
-
-        let mut token_holding = match self.tokens().get(&token_info.symbol)? {
-            Some(holding) => holding,
-            None => {
-                let holding = OtherTokenHolding::init(token_info.clone());
-                self.tokens()
-                    .insert(token_info.symbol.clone(), holding.clone())?;
-                holding
-            }
-        };
-
-        let (token_amount, tx_sig) =
-            swap_from_sol(&token_info.address.to_string(), sol_amount).await?;
-        token_holding.update_buy(sol_amount, token_amount);
+    /// Updates the holding and logs a buy that landed on chain, costing the
+    /// fees in when the fill is known.
+    fn apply_buy(
+        &self,
+        token_info: &TokenInfo,
+        sol_amount: u64,
+        outcome: &SwapOutcome,
+    ) -> anyhow::Result<()> {
+        let mut token_holding = self
+            .tokens()
+            .get(&token_info.address)?
+            .unwrap_or_else(|| OtherTokenHolding::init(token_info.clone()));
+        log_fill(token_info, outcome);
+        let sol_spent = outcome.in_amount(IntentKind::Buy, sol_amount);
+        token_holding.update_buy(sol_spent, outcome.out_amount);
 
         // action log
         PortfolioAction::buy(
             token_info.address.to_string(),
-            token_amount,
-            sol_amount,
-            tx_sig.to_string(),
+            outcome.out_amount,
+            sol_spent,
+            outcome.sig.to_string(),
         )
+        .with_fill(outcome.fill)
+        .with_settings(outcome.settings)
         .log();
 
-        self.tokens()
-            .insert(token_info.symbol.clone(), token_holding)?;
+        self.tokens().insert(token_info.address, token_holding)?;
+        Ok(())
+    }
+
+    /// Settles the intent of a swap that returned an error, which may still
+    /// have landed if it was sent.
+    async fn settle_failed_swap(&self, intent: &mut TradeIntent) {
+        match self.reconcile_intent(intent).await {
+            Ok(()) => tracing::info!("Settled failed swap: {}", intent),
+            Err(e) => tracing::error!("Failed to settle swap {}: {}", intent, e),
+        }
+    }
+
+    /// Settles the intents left unsettled by a crash, from the status of
+    /// their transactions on chain.
+    pub async fn reconcile_intents(&self) -> anyhow::Result<()> {
+        for mut intent in TradeJournal::get().unsettled() {
+            tracing::info!("Reconciling {}", intent);
+            match self.reconcile_intent(&mut intent).await {
+                Ok(()) => tracing::info!("Reconciled {}", intent),
+                Err(e) => tracing::error!("Failed to reconcile {}: {}", intent, e),
+            }
+        }
+
         Ok(())
     }
 
-    pub async fn buy_jimmy(&self, sol_amount: u64) -> anyhow::Result<()> {
+    async fn reconcile_intent(&self, intent: &mut TradeIntent) -> anyhow::Result<()> {
+        // A transaction not found after its blockhash expired never lands
+        const BLOCKHASH_EXPIRY_SECS: u64 = 150;
+
+        let journal = TradeJournal::get();
+        let IntentState::Submitted { expected_out, .. } = intent.state else {
+            if intent.state == IntentState::Pending {
+                journal.fail(intent, "Transaction never sent")?;
+            }
+            return Ok(());
+        };
+        let sig = intent
+            .signature()
+            .ok_or(anyhow::anyhow!("Invalid signature of {}", intent))?;
+
+        // Paper swaps are filled as soon as they are submitted
+        if !Config::get().paper_trading {
+            let mut status = self.executor.signature_status(&sig)?;
+            let age = get_cur_timestamp().saturating_sub(intent.created_at);
+            if status.is_none() && age < BLOCKHASH_EXPIRY_SECS {
+                tokio::time::sleep(Duration::from_secs(BLOCKHASH_EXPIRY_SECS - age)).await;
+                status = self.executor.signature_status(&sig)?;
+            }
+
+            match status {
+                Some(Ok(())) => {}
+                Some(Err(e)) => return journal.fail(intent, e),
+                None => return journal.fail(intent, "Transaction not found on chain"),
+            }
+        }
+
+        // The quoted output is the best known amount of a swap found on chain
+        let fill = if Config::get().paper_trading {
+            None
+        } else {
+            self.read_fill(&sig, &intent.token_info.address, intent.kind, expected_out)
+                .await
+        };
+        let outcome = SwapOutcome::new(intent.kind, expected_out, sig, fill);
+        match intent.kind {
+            IntentKind::Buy => self.apply_buy(&intent.token_info, intent.in_amount, &outcome)?,
+            IntentKind::Sell => self.apply_sell(&intent.token_info, intent.in_amount, &outcome)?,
+        }
+        journal.complete(intent, &sig, outcome.out_amount)
+    }
+
+    /// Buys JIMMY, returning the amount bought and the swap signature.
+    pub async fn buy_jimmy(&self, sol_amount: u64) -> anyhow::Result<(u64, Signature)> {
         let sol_balance = self.sol_balance().await?;
         if sol_balance < sol_amount {
             return Err(anyhow::anyhow!("Not enough SOL to buy"));
         }
 
-        let (jimmy_amount, sig) =
-            swap_from_sol(&self.jimmy_token.mint.to_string(), sol_amount).await?;
+        let decimals = Config::get().token_decimals;
+        let outcome = self
+            .swap_from_sol(&self.jimmy_token.mint, decimals, None, sol_amount, None)
+            .await?;
 
         // action log
         PortfolioAction::buy(
             self.jimmy_token.mint.to_string(),
-            jimmy_amount,
-            sol_amount,
-            sig.to_string(),
+            outcome.out_amount,
+            outcome.in_amount(IntentKind::Buy, sol_amount),
+            outcome.sig.to_string(),
         )
+        .with_fill(outcome.fill)
+        .with_settings(outcome.settings)
         .log();
 
-        Ok(())
+        Ok((outcome.out_amount, outcome.sig))
     }
 
+    /// Sells JIMMY for SOL, never touching the JIMMY held from the buybacks.
     pub async fn sell_jimmy(&self, jimmy_amount: u64) -> anyhow::Result<()> {
-        let jimmy_balance = self.jimmy_balance().await?;
-        if jimmy_balance < jimmy_amount {
+        let sellable = self
+            .jimmy_balance()
+            .await?
+            .saturating_sub(Buyback::get().held_jimmy()?);
+        if sellable < jimmy_amount {
             return Err(anyhow::anyhow!("Not enough JIMMY to sell"));
         }
 
-        let (sol_amount, sig) =
-            swap_to_sol(&self.jimmy_token.mint.to_string(), jimmy_amount).await?;
+        let decimals = Config::get().token_decimals;
+        let outcome = self
+            .swap_to_sol(&self.jimmy_token.mint, decimals, None, jimmy_amount, None)
+            .await?;
 
         // action log
         PortfolioAction::sell(
             self.jimmy_token.mint.to_string(),
-            jimmy_amount,
-            sol_amount,
-            sig.to_string(),
+            outcome.in_amount(IntentKind::Sell, jimmy_amount),
+            outcome.out_amount,
+            outcome.sig.to_string(),
         )
+        .with_fill(outcome.fill)
+        .with_settings(outcome.settings)
         .log();
 
         Ok(())
     }
 }
 
+/// Whether a swap failed for its blockhash expiring before it landed.
+fn is_expired(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<SendError>() == Some(&SendError::BlockhashExpired)
+}
+
+/// A swap that was sent, with its fill when it could be read.
+#[derive(Debug, Clone, Copy)]
+struct SwapOutcome {
+    /// Output received, raw tokens for a buy and lamports net of the fees for
+    /// a sell, the quoted output when the fill could not be read
+    out_amount: u64,
+    sig: Signature,
+    fill: Option<SwapFill>,
+    /// Slippage and priority fee the swap was sent with
+    settings: Option<SwapSettings>,
+}
+
+impl SwapOutcome {
+    /// Outcome of a swap quoted at `quoted_out`, trusting its fill over the
+    /// quote when known.
+    fn new(kind: IntentKind, quoted_out: u64, sig: Signature, fill: Option<SwapFill>) -> Self {
+        let out_amount = fill
+            .map(|fill| match kind {
+                IntentKind::Buy => fill.token_amount,
+                IntentKind::Sell => fill.net_sol,
+            })
+            .unwrap_or(quoted_out);
+        Self {
+            out_amount,
+            sig,
+            fill,
+            settings: None,
+        }
+    }
+
+    /// Input spent, lamports with the fees for a buy and raw tokens for a
+    /// sell, the requested `in_amount` when the fill could not be read.
+    fn in_amount(&self, kind: IntentKind, in_amount: u64) -> u64 {
+        self.fill
+            .map(|fill| match kind {
+                IntentKind::Buy => fill.net_sol,
+                IntentKind::Sell => fill.token_amount,
+            })
+            .unwrap_or(in_amount)
+    }
+}
+
+fn log_fill(token_info: &TokenInfo, outcome: &SwapOutcome) {
+    if let Some(fill) = outcome.fill {
+        tracing::info!(
+            "Filled {} at {} SOL effective price, {} lamports of fees, {:.3}% slippage",
+            token_info.symbol,
+            fill.effective_price(token_info.decimals),
+            fill.fee_lamports,
+            fill.slippage() * 100.0
+        );
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct JimmyHolding {
     pub mint: Pubkey,
+    pub pool_id: Option<Pubkey>,
 }
 
 #[cfg(test)]
@@ -297,6 +927,7 @@ mod tests {
     use rand::Rng;
 
     use crate::{
+        cost_basis::CostBasis,
         portfolio::{OtherTokenHolding, Portfolio},
         token::{store::SolanaTokenStore, structs::TokenInfo},
         LAMPORTS_PER_SOL,
@@ -330,13 +961,172 @@ mod tests {
         };
 
         holding.update_buy(10_i32.pow(9) as u64, 10_i32.pow(6) as u64);
-        holding.update_sell(10_i32.pow(9) as u64, 5 * 10_i32.pow(5) as u64);
+        holding.update_sell(
+            10_i32.pow(9) as u64,
+            5 * 10_i32.pow(5) as u64,
+            CostBasis::Lifo,
+        );
 
         let profit = holding.profit_margin(1.0);
         println!("Profit: {}", profit);
         assert_eq!(profit, 0.5);
     }
 
+    #[test]
+    fn test_swap_outcome_amounts() {
+        use super::SwapOutcome;
+        use crate::{fill::SwapFill, journal::IntentKind};
+        use solana_sdk::signature::Signature;
+
+        // Quoted 2 tokens for 1 SOL, filled 1.98 for 1.002 SOL with the fees
+        let fill = SwapFill {
+            fee_lamports: 5_000,
+            net_sol: 1_002_044_280,
+            token_amount: 1_980_000,
+            quoted_out: 2_000_000,
+            actual_out: 1_980_000,
+        };
+        let buy = SwapOutcome::new(IntentKind::Buy, 2_000_000, Signature::default(), Some(fill));
+        assert_eq!(buy.out_amount, 1_980_000);
+        assert_eq!(
+            buy.in_amount(IntentKind::Buy, LAMPORTS_PER_SOL),
+            1_002_044_280
+        );
+
+        let sell = SwapOutcome::new(IntentKind::Sell, 990_000_000, Signature::default(), None);
+        assert_eq!(sell.out_amount, 990_000_000);
+        assert_eq!(sell.in_amount(IntentKind::Sell, 1_980_000), 1_980_000);
+    }
+
+    #[tokio::test]
+    async fn test_mock_buy_and_sell() {
+        use crate::{portfolio::JimmyHolding, swap::mock::MockAmm};
+        use solana_sdk::signer::{keypair::Keypair, Signer};
+
+        // The example settings and a scratch store, nothing leaves the process
+        std::env::set_var("STORE_PATH", "/tmp/test_portfolio_store");
+        dotenv::from_filename(".env.example").ok();
+
+        // Traded as a JIMMY without a pool, so no price source is asked
+        let mint = Keypair::new().pubkey();
+        let token_info = TokenInfo {
+            address: mint,
+            symbol: "TEST".to_string(),
+            name: "Test".to_string(),
+            decimals: 6,
+            coingecko_id: None,
+        };
+        let amm = MockAmm::new(25);
+        amm.add_pool(mint, 1_000 * LAMPORTS_PER_SOL, 1_000_000_000_000);
+        let portfolio = Portfolio::with_executor(
+            JimmyHolding {
+                mint,
+                pool_id: None,
+            },
+            Box::new(amm),
+        )
+        .unwrap();
+
+        portfolio
+            .journaled_buy(&token_info, LAMPORTS_PER_SOL)
+            .await
+            .unwrap();
+        let holding = portfolio.tokens().get(&mint).unwrap().unwrap();
+        assert_eq!(holding.holding_amount(), 996_505_985);
+
+        // Selling back half loses the fees and the price impact
+        portfolio
+            .sell_token(&token_info, 498_252_992)
+            .await
+            .unwrap();
+        let holding = portfolio.tokens().get(&mint).unwrap().unwrap();
+        assert_eq!(holding.holding_amount(), 498_252_993);
+        assert!(holding.total_pnl < 0.0);
+        assert!(portfolio.sell_token(&token_info, u64::MAX).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_simulation_fails_at_once() {
+        use crate::{
+            journal::{IntentState, TradeJournal},
+            portfolio::JimmyHolding,
+            swap::{mock::MockAmm, sender::SendError, PreparedSwap, SwapExecutor},
+        };
+        use solana_sdk::{
+            pubkey::Pubkey,
+            signature::Signature,
+            signer::{keypair::Keypair, Signer},
+        };
+
+        /// The mock AMM, with every simulation failing on slippage.
+        struct Rejecting(MockAmm);
+
+        #[async_trait::async_trait]
+        impl SwapExecutor for Rejecting {
+            fn name(&self) -> &'static str {
+                "rejecting"
+            }
+
+            async fn prepare(
+                &self,
+                input_mint: &Pubkey,
+                output_mint: &Pubkey,
+                amount: u64,
+                slippage_bps: u64,
+            ) -> anyhow::Result<PreparedSwap> {
+                self.0
+                    .prepare(input_mint, output_mint, amount, slippage_bps)
+                    .await
+            }
+
+            async fn simulate(&self, _prepared: &PreparedSwap) -> anyhow::Result<()> {
+                Err(SendError::SlippageExceeded.into())
+            }
+
+            async fn send(&self, _prepared: &PreparedSwap) -> anyhow::Result<Signature> {
+                unreachable!("a rejected swap is never sent")
+            }
+        }
+
+        std::env::set_var("STORE_PATH", "/tmp/test_portfolio_store");
+        dotenv::from_filename(".env.example").ok();
+
+        let mint = Keypair::new().pubkey();
+        let token_info = TokenInfo {
+            address: mint,
+            symbol: "TEST".to_string(),
+            name: "Test".to_string(),
+            decimals: 6,
+            coingecko_id: None,
+        };
+        let amm = MockAmm::new(25);
+        amm.add_pool(mint, 1_000 * LAMPORTS_PER_SOL, 1_000_000_000_000);
+        let portfolio = Portfolio::with_executor(
+            JimmyHolding {
+                mint,
+                pool_id: None,
+            },
+            Box::new(Rejecting(amm)),
+        )
+        .unwrap();
+
+        // Never broadcast, so nothing is waited for on chain
+        let bought = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            portfolio.journaled_buy(&token_info, LAMPORTS_PER_SOL),
+        )
+        .await
+        .expect("a rejected simulation must not wait for the blockhash expiry");
+        assert!(bought.is_err());
+
+        let intent = TradeJournal::get()
+            .list()
+            .into_iter()
+            .rfind(|intent| intent.token_info.address == mint)
+            .unwrap();
+        assert!(matches!(intent.state, IntentState::Failed { .. }));
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_more_profit() {
         init();
@@ -380,6 +1170,7 @@ mod tests {
             holding.update_sell(
                 has_sol,
                 (holding.holding_amount() as f64 * sell_part) as u64,
+                CostBasis::Lifo,
             );
             tracing::info!("holding: {:?}", holding);
             tracing::info!(
@@ -419,7 +1210,7 @@ mod tests {
         );
         let usdc_balance = portfolio
             .tokens()
-            .get(&"USDC".to_string())
+            .get(&usdc.address)
             .unwrap()
             .unwrap()
             .holding_amount();
@@ -434,7 +1225,7 @@ mod tests {
 
         let usdc_balance = portfolio
             .tokens()
-            .get(&"USDC".to_string())
+            .get(&usdc.address)
             .unwrap()
             .unwrap()
             .holding_amount();
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -7,6 +8,7 @@ use tokio::sync::RwLock;
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
+use super::{PriceId, PriceProvider};
 use crate::config::Config;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -196,6 +198,49 @@ impl CoinGeckoProvider {
     }
 }
 
+#[async_trait]
+impl PriceProvider for CoinGeckoProvider {
+    fn name(&self) -> &'static str {
+        "coingecko"
+    }
+
+    async fn prices(&self, ids: &[PriceId], vs_currency: &str) -> Result<Vec<Option<f64>>> {
+        let coin_ids = ids
+            .iter()
+            .filter_map(|id| id.coingecko_id.as_deref())
+            .collect::<Vec<_>>();
+        if coin_ids.is_empty() {
+            return Ok(vec![None; ids.len()]);
+        }
+
+        let prices = self.get_prices_by_ids(&coin_ids, vs_currency).await?;
+        Ok(ids
+            .iter()
+            .map(|id| {
+                let coin_id = id.coingecko_id.as_deref()?;
+                coin_ids
+                    .iter()
+                    .position(|c| *c == coin_id)
+                    .map(|i| prices[i])
+            })
+            .collect())
+    }
+
+    async fn history(
+        &self,
+        id: &PriceId,
+        vs_currency: &str,
+        days: u32,
+    ) -> Result<HistoricalPriceResponse> {
+        let coin_id = id
+            .coingecko_id
+            .as_deref()
+            .ok_or(anyhow::anyhow!("No coingecko id found for {}", id.mint))?;
+        self.get_historical_price_by_id(coin_id, vs_currency, days)
+            .await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
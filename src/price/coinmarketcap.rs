@@ -1 +1,122 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::RwLock;
 
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::{PriceId, PriceProvider};
+use crate::constant::{SOL_COINMARKETCAP_ID, SOL_MINT};
+
+/// Latest quotes of the CoinMarketCap API, by the CoinMarketCap id of the
+/// token contract.
+pub struct CoinMarketCapProvider {
+    client: Client,
+    api_key: String,
+    /// CoinMarketCap ids by mint, None for the mints it does not list
+    ids: RwLock<HashMap<Pubkey, Option<u64>>>,
+}
+
+impl CoinMarketCapProvider {
+    const INFO_URL: &'static str = "https://pro-api.coinmarketcap.com/v2/cryptocurrency/info";
+    const QUOTES_URL: &'static str =
+        "https://pro-api.coinmarketcap.com/v2/cryptocurrency/quotes/latest";
+
+    pub fn new(api_key: String) -> Self {
+        let sol_mint = Pubkey::from_str(SOL_MINT).expect("Invalid SOL mint");
+        Self {
+            client: Client::new(),
+            api_key,
+            ids: RwLock::new(HashMap::from([(sol_mint, Some(SOL_COINMARKETCAP_ID))])),
+        }
+    }
+
+    /// CoinMarketCap id of the token at the contract address `mint`.
+    async fn get_id(&self, mint: &Pubkey) -> Result<Option<u64>> {
+        if let Some(id) = self.ids.read().await.get(mint) {
+            return Ok(*id);
+        }
+
+        let response = self
+            .client
+            .get(Self::INFO_URL)
+            .header("X-CMC_PRO_API_KEY", &self.api_key)
+            .query(&[("address", mint.to_string())])
+            .send()
+            .await?;
+        // An address it does not list is a bad request
+        let id = match response.status() {
+            StatusCode::BAD_REQUEST => None,
+            status if status.is_success() => {
+                let json: Value = response.json().await?;
+                json["data"]
+                    .as_object()
+                    .and_then(|coins| coins.values().next())
+                    .and_then(|coin| coin["id"].as_u64())
+            }
+            status => {
+                return Err(anyhow::anyhow!(
+                    "CoinMarketCap info request failed: {}",
+                    status
+                ))
+            }
+        };
+
+        self.ids.write().await.insert(*mint, id);
+        Ok(id)
+    }
+}
+
+#[async_trait]
+impl PriceProvider for CoinMarketCapProvider {
+    fn name(&self) -> &'static str {
+        "coinmarketcap"
+    }
+
+    async fn prices(&self, ids: &[PriceId], vs_currency: &str) -> Result<Vec<Option<f64>>> {
+        let mut cmc_ids = vec![];
+        for id in ids {
+            cmc_ids.push(self.get_id(&id.mint).await?);
+        }
+        let mut requested = cmc_ids.iter().flatten().copied().collect::<Vec<_>>();
+        requested.sort();
+        requested.dedup();
+        if requested.is_empty() {
+            return Ok(vec![None; ids.len()]);
+        }
+
+        let convert = vs_currency.to_uppercase();
+        let response = self
+            .client
+            .get(Self::QUOTES_URL)
+            .header("X-CMC_PRO_API_KEY", &self.api_key)
+            .query(&[
+                (
+                    "id",
+                    requested
+                        .iter()
+                        .map(u64::to_string)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+                ("convert", convert.clone()),
+            ])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "CoinMarketCap quotes request failed: {}",
+                response.status()
+            ));
+        }
+        let json: Value = response.json().await?;
+
+        Ok(cmc_ids
+            .into_iter()
+            .map(|cmc_id| json["data"][cmc_id?.to_string()]["quote"][&convert]["price"].as_f64())
+            .collect())
+    }
+}
@@ -1,22 +1,55 @@
 use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
 
-pub async fn get_price(tokens: Vec<String>) -> Result<Vec<f64>> {
-    // // Jupiter API endpoint
-    // let api_url = "https://api.jup.ag/price/v2";
+use super::{PriceId, PriceProvider};
+use crate::client::get_http_client;
+use crate::constant::*;
 
-    // // Tokens to fetch prices for (using mint addresses or symbols)
-    // let ids = tokens.join(",");
-    // let show_extra_info = false;
-    // // vsToken cannot be used with showExtraInfo when it's true. A response 400: Bad request would be returned.
-    // // If we don't specify vsToken, the price will be in USDC.
-    // let vs_token = "SOL";
+/// Prices of the Jupiter Price API, derived from the on-chain pools.
+pub struct JupiterPriceProvider;
 
-    // let url = format!("{api_url}?ids={ids}&showExtraInfo={show_extra_info}");
+impl JupiterPriceProvider {
+    const PRICE_URL: &'static str = "https://api.jup.ag/price/v2";
+}
+
+#[async_trait]
+impl PriceProvider for JupiterPriceProvider {
+    fn name(&self) -> &'static str {
+        "jupiter"
+    }
 
-    // let response = reqwest::get(&url).await?;
-    // let json: Value = response.json().await?;
+    async fn prices(&self, ids: &[PriceId], vs_currency: &str) -> Result<Vec<Option<f64>>> {
+        let mints = ids.iter().map(|id| id.mint.to_string()).collect::<Vec<_>>();
+        let mut params = vec![("ids", mints.join(","))];
+        // Priced in USDC unless another vsToken is given
+        match vs_currency {
+            USD_CURRENCY => {}
+            SOL_CURRENCY => params.push(("vsToken", SOL_MINT.to_string())),
+            _ => return Err(anyhow::anyhow!("Unsupported currency: {}", vs_currency)),
+        }
 
-    // tracing::info!("Jupiter price response: {}", json);
+        let response = get_http_client()
+            .get(Self::PRICE_URL)
+            .query(&params)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Jupiter price request failed: {}",
+                response.status()
+            ));
+        }
+        let json: Value = response.json().await?;
 
-    todo!()
+        // Unknown mints are null, and the prices are strings
+        Ok(mints
+            .iter()
+            .map(|mint| {
+                json["data"][mint]["price"]
+                    .as_str()
+                    .and_then(|price| price.parse::<f64>().ok())
+            })
+            .collect())
+    }
 }
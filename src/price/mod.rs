@@ -1,3 +1,300 @@
+//! Token prices behind the [`PriceProvider`] trait: CoinGecko, the Jupiter
+//! Price API and CoinMarketCap, and the [`PriceAggregator`] taking the median
+//! of those answering.
 pub mod coingecko;
 pub mod coinmarketcap;
 pub mod jupiter;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use solana_sdk::pubkey::Pubkey;
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::config::Config;
+use crate::constant::*;
+use crate::token::structs::TokenInfo;
+use coingecko::{CoinGeckoProvider, HistoricalPriceResponse};
+use coinmarketcap::CoinMarketCapProvider;
+use jupiter::JupiterPriceProvider;
+
+/// A token as the price sources know it: by mint for Jupiter and
+/// CoinMarketCap and by id for CoinGecko.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceId {
+    pub mint: Pubkey,
+    pub symbol: Option<String>,
+    pub coingecko_id: Option<String>,
+}
+
+impl PriceId {
+    pub fn from_mint(mint: &Pubkey, coingecko_id: Option<&str>) -> Self {
+        Self {
+            mint: *mint,
+            symbol: None,
+            coingecko_id: coingecko_id.map(str::to_string),
+        }
+    }
+
+    pub fn sol() -> Self {
+        Self {
+            mint: Pubkey::from_str(SOL_MINT).expect("Invalid SOL mint"),
+            symbol: Some("SOL".to_string()),
+            coingecko_id: Some(SOL_COINGECKO_ID.to_string()),
+        }
+    }
+}
+
+impl From<&TokenInfo> for PriceId {
+    fn from(token_info: &TokenInfo) -> Self {
+        Self {
+            mint: token_info.address,
+            symbol: Some(token_info.symbol.clone()),
+            coingecko_id: token_info.coingecko_id.clone(),
+        }
+    }
+}
+
+/// A source of token prices, in `usd` or `sol` per UI unit of the token.
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Prices of the tokens, None for those the source does not know.
+    async fn prices(&self, ids: &[PriceId], vs_currency: &str) -> Result<Vec<Option<f64>>>;
+
+    async fn price(&self, id: &PriceId, vs_currency: &str) -> Result<f64> {
+        self.prices(std::slice::from_ref(id), vs_currency)
+            .await?
+            .into_iter()
+            .next()
+            .flatten()
+            .ok_or(anyhow::anyhow!("No {} price of {}", self.name(), id.mint))
+    }
+
+    /// Price, market cap and volume series over the last `days`.
+    async fn history(
+        &self,
+        id: &PriceId,
+        _vs_currency: &str,
+        _days: u32,
+    ) -> Result<HistoricalPriceResponse> {
+        Err(anyhow::anyhow!(
+            "No historical prices of {} on {}",
+            id.mint,
+            self.name()
+        ))
+    }
+}
+
+/// Median of the prices quoted by the sources of a token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatedPrice {
+    pub price: f64,
+    pub sources: Vec<&'static str>,
+    /// Spread between the highest and lowest quote, over the median
+    pub dispersion: f64,
+    /// Whether the dispersion is above the tolerated one
+    pub disagreement: bool,
+}
+
+impl AggregatedPrice {
+    pub fn from_quotes(quotes: &[(&'static str, f64)], max_dispersion: f64) -> Option<Self> {
+        let quotes = quotes
+            .iter()
+            .filter(|(_, price)| price.is_finite() && *price > 0.0)
+            .collect::<Vec<_>>();
+        if quotes.is_empty() {
+            return None;
+        }
+        let mut prices = quotes.iter().map(|(_, price)| *price).collect::<Vec<_>>();
+        prices.sort_by(|a, b| a.total_cmp(b));
+
+        let mid = prices.len() / 2;
+        let price = if prices.len() % 2 == 0 {
+            (prices[mid - 1] + prices[mid]) / 2.0
+        } else {
+            prices[mid]
+        };
+        let dispersion = (prices[prices.len() - 1] - prices[0]) / price;
+
+        Some(Self {
+            price,
+            sources: quotes.iter().map(|(source, _)| *source).collect(),
+            dispersion,
+            disagreement: max_dispersion > 0.0 && dispersion > max_dispersion,
+        })
+    }
+}
+
+/// Asks every source and takes the median price, so a failing source falls
+/// back on the others. Historical prices come from the first source having
+/// them.
+pub struct PriceAggregator {
+    providers: Vec<Box<dyn PriceProvider>>,
+    /// A threshold of 0 never flags a disagreement
+    max_dispersion: f64,
+}
+
+impl PriceAggregator {
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<PriceAggregator> = OnceLock::new();
+        INSTANCE.get_or_init(|| {
+            let config = Config::get();
+            let mut providers: Vec<Box<dyn PriceProvider>> = vec![];
+            if let Some(api_key) = config.coingecko_api_key.clone() {
+                providers.push(Box::new(CoinGeckoProvider::new(api_key)));
+            }
+            providers.push(Box::new(JupiterPriceProvider));
+            if let Some(api_key) = config.coinmarketcap_api_key.clone() {
+                providers.push(Box::new(CoinMarketCapProvider::new(api_key)));
+            }
+            PriceAggregator::new(providers, config.price_max_dispersion)
+        })
+    }
+
+    pub fn new(providers: Vec<Box<dyn PriceProvider>>, max_dispersion: f64) -> Self {
+        Self {
+            providers,
+            max_dispersion,
+        }
+    }
+
+    /// Aggregated prices of the tokens, None for those no source knows.
+    pub async fn quotes(
+        &self,
+        ids: &[PriceId],
+        vs_currency: &str,
+    ) -> Result<Vec<Option<AggregatedPrice>>> {
+        let mut quotes = vec![vec![]; ids.len()];
+        let mut errors = vec![];
+        for provider in &self.providers {
+            match provider.prices(ids, vs_currency).await {
+                Ok(prices) => {
+                    for (quotes, price) in quotes.iter_mut().zip(prices) {
+                        if let Some(price) = price {
+                            quotes.push((provider.name(), price));
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to get prices from {}: {}", provider.name(), e);
+                    errors.push(format!("{}: {}", provider.name(), e));
+                }
+            }
+        }
+        if errors.len() == self.providers.len() {
+            return Err(anyhow::anyhow!(
+                "Every price source failed: {}",
+                errors.join(", ")
+            ));
+        }
+
+        Ok(ids
+            .iter()
+            .zip(quotes)
+            .map(|(id, quotes)| {
+                let aggregated = AggregatedPrice::from_quotes(&quotes, self.max_dispersion)?;
+                if aggregated.disagreement {
+                    tracing::warn!(
+                        "Price sources disagree on {} by {:.2}%: {:?}",
+                        id.symbol.as_deref().unwrap_or(&id.mint.to_string()),
+                        aggregated.dispersion * 100.0,
+                        quotes
+                    );
+                }
+                Some(aggregated)
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl PriceProvider for PriceAggregator {
+    fn name(&self) -> &'static str {
+        "aggregate"
+    }
+
+    async fn prices(&self, ids: &[PriceId], vs_currency: &str) -> Result<Vec<Option<f64>>> {
+        Ok(self
+            .quotes(ids, vs_currency)
+            .await?
+            .into_iter()
+            .map(|quote| quote.map(|quote| quote.price))
+            .collect())
+    }
+
+    async fn history(
+        &self,
+        id: &PriceId,
+        vs_currency: &str,
+        days: u32,
+    ) -> Result<HistoricalPriceResponse> {
+        let mut errors = vec![];
+        for provider in &self.providers {
+            match provider.history(id, vs_currency, days).await {
+                Ok(history) => return Ok(history),
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        Err(anyhow::anyhow!(
+            "No historical prices of {}: {}",
+            id.mint,
+            errors.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedPrice(&'static str, Option<f64>);
+
+    #[async_trait]
+    impl PriceProvider for FixedPrice {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn prices(&self, ids: &[PriceId], _vs_currency: &str) -> Result<Vec<Option<f64>>> {
+            match self.1 {
+                Some(price) => Ok(vec![Some(price); ids.len()]),
+                None => Err(anyhow::anyhow!("{} is down", self.0)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_price_aggregator() {
+        let sol = PriceId::sol();
+        let aggregator = PriceAggregator::new(
+            vec![
+                Box::new(FixedPrice("down", None)),
+                Box::new(FixedPrice("a", Some(100.0))),
+                Box::new(FixedPrice("b", Some(102.0))),
+                Box::new(FixedPrice("c", Some(150.0))),
+            ],
+            0.05,
+        );
+        let quote = aggregator
+            .quotes(std::slice::from_ref(&sol), USD_CURRENCY)
+            .await
+            .unwrap()[0]
+            .clone()
+            .unwrap();
+        assert_eq!(quote.price, 102.0);
+        assert_eq!(quote.sources, vec!["a", "b", "c"]);
+        assert!(quote.disagreement);
+        assert!(aggregator.history(&sol, USD_CURRENCY, 1).await.is_err());
+
+        let quote = AggregatedPrice::from_quotes(&[("a", 100.0), ("b", 102.0)], 0.05).unwrap();
+        assert_eq!(quote.price, 101.0);
+        assert!(!quote.disagreement);
+        assert_eq!(AggregatedPrice::from_quotes(&[], 0.05), None);
+
+        let down = PriceAggregator::new(vec![Box::new(FixedPrice("down", None))], 0.05);
+        assert!(down.price(&sol, USD_CURRENCY).await.is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::config::Config;
+use crate::portfolio::PortfolioValuation;
+use crate::strategy::Trade;
+use crate::token::structs::TokenInfo;
+
+#[derive(Debug, Clone)]
+pub enum Order {
+    /// Spend `sol_amount` lamports on the token
+    Buy { token: TokenInfo, sol_amount: u64 },
+    /// Sell `amount` raw tokens
+    Sell { token: TokenInfo, amount: u64 },
+}
+
+impl std::fmt::Display for Order {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Order::Buy { token, sol_amount } => {
+                write!(f, "buy {} with {} lamports", token.symbol, sol_amount)
+            }
+            Order::Sell { token, amount } => write!(f, "sell {} {}", amount, token.symbol),
+        }
+    }
+}
+
+/// Moves the selected holdings toward the weights of the trades.
+pub struct Rebalancer {
+    /// Relative drift from the target value tolerated without trading
+    pub drift_tolerance: f64,
+}
+
+impl Rebalancer {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            drift_tolerance: config.rebalance_drift_tolerance,
+        }
+    }
+
+    /// Plans the orders bringing the selected holdings to their targets, sells
+    /// first.
+    ///
+    /// The capital allocated is `budget` plus the current value of the
+    /// selected holdings, both in lamports, and each trade targets its weight
+    /// of it. The pending TWAP slices of a token count as held. Holdings
+    /// within the drift tolerance of their target are left untouched, so the
+    /// buys are scaled down to fit in `budget` plus the proceeds of the sells.
+    pub fn plan(
+        &self,
+        trades: &[Trade],
+        budget: f64,
+        valuation: &PortfolioValuation,
+    ) -> Vec<Order> {
+        let held_value: f64 = trades
+            .iter()
+            .map(|t| {
+                let holding = valuation.holding(&t.token);
+                holding.map(|h| h.value()).unwrap_or_default() + valuation.committed(&t.token)
+            })
+            .sum();
+        let capital = budget + held_value;
+
+        let mut sells = vec![];
+        let mut proceeds = 0.0;
+        let mut buys = vec![];
+        for trade in trades {
+            let target = capital * trade.weight;
+            let holding = valuation.holding(&trade.token);
+            let current =
+                holding.map(|h| h.value()).unwrap_or_default() + valuation.committed(&trade.token);
+
+            let drift = if target > 0.0 {
+                (current - target).abs() / target
+            } else {
+                f64::INFINITY
+            };
+            if drift <= self.drift_tolerance {
+                continue;
+            }
+
+            match holding {
+                Some(holding) if current > target && holding.value() > 0.0 => {
+                    let share = ((current - target) / holding.value()).min(1.0);
+                    let amount = (holding.holding.holding_amount() as f64 * share).floor() as u64;
+                    if amount > 0 {
+                        proceeds += holding.value() * amount as f64
+                            / holding.holding.holding_amount() as f64;
+                        sells.push(Order::Sell {
+                            token: trade.token.clone(),
+                            amount,
+                        });
+                    }
+                }
+                _ => buys.push((trade.token.clone(), target - current)),
+            }
+        }
+
+        let wanted: f64 = buys.iter().map(|(_, sol_amount)| sol_amount).sum();
+        let scale = if wanted > budget + proceeds {
+            (budget + proceeds) / wanted
+        } else {
+            1.0
+        };
+        sells.extend(buys.into_iter().filter_map(|(token, sol_amount)| {
+            let sol_amount = (sol_amount * scale).floor() as u64;
+            (sol_amount > 0).then_some(Order::Buy { token, sol_amount })
+        }));
+        sells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+    use crate::constant::LAMPORTS_PER_SOL;
+    use crate::portfolio::{HoldingValuation, OtherTokenHolding};
+    use std::collections::HashMap;
+
+    const SOL: f64 = LAMPORTS_PER_SOL as f64;
+
+    fn token(symbol: &str) -> TokenInfo {
+        TokenInfo {
+            address: Pubkey::new_unique(),
+            decimals: 9,
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            coingecko_id: None,
+        }
+    }
+
+    /// Holdings of `amount` tokens priced at 1 SOL.
+    fn valuation(held: &[(TokenInfo, f64)]) -> PortfolioValuation {
+        let holdings = held
+            .iter()
+            .map(|(token, amount)| {
+                let mut holding = OtherTokenHolding::init(token.clone());
+                holding.update_buy((amount * SOL) as u64, (amount * SOL) as u64);
+                HoldingValuation {
+                    holding,
+                    price_in_sol: 1.0,
+                }
+            })
+            .collect();
+
+        PortfolioValuation {
+            sol_balance: (100.0 * SOL) as u64,
+            holdings,
+            sol_price_usd: 100.0,
+            committed: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_rebalance_orders() {
+        let (a, b, c) = (token("A"), token("B"), token("C"));
+        let valuation = valuation(&[(a.clone(), 15.0), (b.clone(), 5.2)]);
+        let trades = vec![
+            Trade {
+                token: a.clone(),
+                weight: 0.2,
+            },
+            Trade {
+                token: b.clone(),
+                weight: 0.2,
+            },
+            Trade {
+                token: c.clone(),
+                weight: 0.6,
+            },
+        ];
+
+        // 4.8 SOL of budget and 20.2 SOL held target 25 SOL, A is overweight,
+        // B within the band keeps its 0.2 SOL over target
+        let rebalancer = Rebalancer {
+            drift_tolerance: 0.1,
+        };
+        let budget = 4.8 * SOL;
+        let orders = rebalancer.plan(&trades, budget, &valuation);
+        assert_eq!(orders.len(), 2);
+
+        let Order::Sell { token, amount } = &orders[0] else {
+            panic!("sells must come first");
+        };
+        assert_eq!(token, &a);
+        assert!(amount.abs_diff((10.0 * SOL) as u64) <= 1);
+        let proceeds = *amount as f64;
+
+        // C is short of its 15 SOL target by what B keeps
+        let Order::Buy { token, sol_amount } = &orders[1] else {
+            panic!("expected a buy");
+        };
+        assert_eq!(token, &c);
+        assert!(*sol_amount as f64 <= budget + proceeds);
+        assert!(sol_amount.abs_diff((14.8 * SOL) as u64) <= 1);
+    }
+
+    #[test]
+    fn test_pending_slices_count_as_held() {
+        let a = token("A");
+        let mut valuation = valuation(&[(a.clone(), 2.0)]);
+        valuation.committed = HashMap::from([(a.address, (6.0 * SOL) as u64)]);
+        let trades = vec![Trade {
+            token: a.clone(),
+            weight: 1.0,
+        }];
+
+        // 2 SOL held and 6 SOL pending of a 10 SOL target
+        let rebalancer = Rebalancer {
+            drift_tolerance: 0.1,
+        };
+        let orders = rebalancer.plan(&trades, 2.0 * SOL, &valuation);
+        let [Order::Buy { sol_amount, .. }] = orders.as_slice() else {
+            panic!("expected a single buy");
+        };
+        assert!(sol_amount.abs_diff((2.0 * SOL) as u64) <= 1);
+    }
+}
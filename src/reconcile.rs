@@ -0,0 +1,198 @@
+//! Reconciliation of the stored holdings with the token accounts of the
+//! wallet, which drift on partial fills, transfers in, airdrops or dust.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use std::collections::HashMap;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::Action;
+use crate::config::Config;
+use crate::cost_basis::CostBasis;
+use crate::portfolio::{OtherTokenHolding, Portfolio};
+use crate::token::jimmy::JimmyToken;
+use crate::wallet::Wallet;
+
+/// A holding whose stored amount differs from the wallet, in raw amounts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoldingDrift {
+    pub token: String,
+    pub symbol: String,
+    pub stored: u64,
+    pub on_chain: u64,
+}
+
+impl std::fmt::Display for HoldingDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} stored {} on chain {}",
+            self.symbol, self.stored, self.on_chain
+        )
+    }
+}
+
+/// Sums the raw amounts of the token accounts by mint.
+pub fn balances_by_mint(accounts: impl IntoIterator<Item = (Pubkey, u64)>) -> HashMap<Pubkey, u64> {
+    let mut balances = HashMap::new();
+    for (mint, raw_amount) in accounts {
+        *balances.entry(mint).or_default() += raw_amount;
+    }
+    balances
+}
+
+pub fn holding_drifts(
+    holdings: &[OtherTokenHolding],
+    balances: &HashMap<Pubkey, u64>,
+) -> Vec<HoldingDrift> {
+    holdings
+        .iter()
+        .filter_map(|holding| {
+            let stored = holding.holding_amount();
+            let on_chain = balances
+                .get(&holding.token_info.address)
+                .copied()
+                .unwrap_or_default();
+            (stored != on_chain).then(|| HoldingDrift {
+                token: holding.token_info.address.to_string(),
+                symbol: holding.token_info.symbol.clone(),
+                stored,
+                on_chain,
+            })
+        })
+        .collect()
+}
+
+/// Mints with a balance in the wallet that no holding tracks.
+pub fn untracked_mints(
+    holdings: &[OtherTokenHolding],
+    balances: &HashMap<Pubkey, u64>,
+    ignored: &[Pubkey],
+) -> Vec<String> {
+    let mut untracked = balances
+        .iter()
+        .filter(|(mint, amount)| {
+            **amount > 0
+                && !ignored.contains(mint)
+                && !holdings.iter().any(|h| h.token_info.address == **mint)
+        })
+        .map(|(mint, _)| mint.to_string())
+        .collect::<Vec<_>>();
+    untracked.sort();
+    untracked
+}
+
+/// Sets the holding to its on-chain amount.
+///
+/// Tokens that showed up are added at no cost, and tokens that vanished are
+/// removed for nothing, realizing their cost as a loss.
+pub fn fix_holding(holding: &mut OtherTokenHolding, on_chain: u64, cost_basis: CostBasis) {
+    let stored = holding.holding_amount();
+    if on_chain > stored {
+        holding.update_buy(0, on_chain - stored);
+    } else if on_chain < stored {
+        holding.update_sell(0, stored - on_chain, cost_basis);
+    }
+}
+
+/// Compares every holding with the token accounts of the wallet, fixes the
+/// drift if `RECONCILE_FIX` is set, and logs a report of it.
+pub async fn reconcile_holdings() -> Result<()> {
+    let config = Config::get();
+    if config.paper_trading {
+        tracing::info!("Paper trading, skip reconciling the holdings with the wallet");
+        return Ok(());
+    }
+
+    let accounts = Wallet::get().get_all_tokens_info().await?;
+    let balances = balances_by_mint(
+        accounts
+            .into_iter()
+            .map(|account| (account.mint, account.raw_amount)),
+    );
+
+    let portfolio = Portfolio::get().await;
+    let holdings = portfolio
+        .tokens()
+        .iter()
+        .map(|(_, holding)| holding.into_owned())
+        .collect::<Vec<_>>();
+
+    let drifts = holding_drifts(&holdings, &balances);
+    let ignored = [
+        JimmyToken::get().await.mint_pubkey(),
+        spl_token::native_mint::id(),
+    ];
+    let untracked = untracked_mints(&holdings, &balances, &ignored);
+    if drifts.is_empty() && untracked.is_empty() {
+        tracing::info!("Holdings match the wallet");
+        return Ok(());
+    }
+
+    for drift in &drifts {
+        tracing::warn!("Holding drift: {}", drift);
+    }
+    for mint in &untracked {
+        tracing::warn!("Untracked token in the wallet: {}", mint);
+    }
+
+    if config.reconcile_fix {
+        for mut holding in holdings {
+            let mint = holding.token_info.address;
+            let Some(drift) = drifts.iter().find(|d| d.token == mint.to_string()) else {
+                continue;
+            };
+            fix_holding(&mut holding, drift.on_chain, config.cost_basis);
+            portfolio.tokens().insert(mint, holding)?;
+        }
+    }
+
+    PortfolioAction::reconcile(drifts, untracked, config.reconcile_fix).log();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::structs::TokenInfo;
+
+    fn holding(address: Pubkey, amount: u64) -> OtherTokenHolding {
+        let mut holding = OtherTokenHolding::init(TokenInfo {
+            address,
+            symbol: "TEST".to_string(),
+            name: "Test".to_string(),
+            decimals: 6,
+            coingecko_id: None,
+        });
+        holding.update_buy(1_000_000, amount);
+        holding
+    }
+
+    #[test]
+    fn test_drifts_and_fix() {
+        let (held, airdropped, jimmy) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let balances = balances_by_mint([(held, 600), (held, 300), (airdropped, 5), (jimmy, 7)]);
+        let mut holdings = vec![holding(held, 1_000)];
+
+        let drifts = holding_drifts(&holdings, &balances);
+        assert_eq!(drifts.len(), 1);
+        assert_eq!((drifts[0].stored, drifts[0].on_chain), (1_000, 900));
+        assert_eq!(
+            untracked_mints(&holdings, &balances, &[jimmy]),
+            vec![airdropped.to_string()]
+        );
+
+        fix_holding(&mut holdings[0], 900, CostBasis::Fifo);
+        assert!(holding_drifts(&holdings, &balances).is_empty());
+        assert!((holdings[0].total_pnl + 100_000.0).abs() < 1e-6);
+
+        fix_holding(&mut holdings[0], 950, CostBasis::Fifo);
+        assert_eq!(holdings[0].holding_amount(), 950);
+    }
+}
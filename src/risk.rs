@@ -0,0 +1,395 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use std::sync::OnceLock;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::Action;
+use crate::config::Config;
+use crate::paper::trading_prefix;
+use crate::portfolio::PortfolioValuation;
+use crate::store::{LocalStore, Store, StoreMap};
+use crate::strategy::Trade;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RiskDecision {
+    Approve,
+    Resize,
+    Veto,
+}
+
+impl std::fmt::Display for RiskDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskDecision::Approve => write!(f, "approved"),
+            RiskDecision::Resize => write!(f, "resized"),
+            RiskDecision::Veto => write!(f, "vetoed"),
+        }
+    }
+}
+
+/// Outcome of the risk review of one trade.
+#[derive(Debug)]
+pub struct RiskCheck {
+    pub trade: Trade,
+    pub decision: RiskDecision,
+    /// Requested SOL amount in lamports
+    pub requested: u64,
+    /// Approved SOL amount in lamports
+    pub approved: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RiskLimits {
+    /// Maximum share of NAV held in a single token
+    pub max_position_share: f64,
+    /// Maximum share of NAV held in non-SOL assets
+    pub max_non_sol_share: f64,
+    pub max_open_positions: usize,
+    /// Drawdown from the NAV peak that halts every buy
+    pub max_drawdown: f64,
+}
+
+impl RiskLimits {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_position_share: config.risk_max_position_share,
+            max_non_sol_share: config.risk_max_non_sol_share,
+            max_open_positions: config.risk_max_open_positions,
+            max_drawdown: config.risk_max_drawdown,
+        }
+    }
+
+    /// Reviews the trades of a round in order.
+    ///
+    /// `budget` is the lamports the trade weights refer to and `peak_nav` the
+    /// highest NAV seen so far, both in lamports. The pending TWAP slices of a
+    /// token count as held.
+    pub fn assess(
+        &self,
+        trades: Vec<Trade>,
+        budget: f64,
+        valuation: &PortfolioValuation,
+        peak_nav: f64,
+    ) -> Vec<RiskCheck> {
+        let nav = valuation.nav();
+        let drawdown = if peak_nav > 0.0 {
+            (peak_nav - nav) / peak_nav
+        } else {
+            0.0
+        };
+
+        let pending_positions = valuation
+            .committed
+            .keys()
+            .filter(|mint| {
+                !valuation
+                    .holdings
+                    .iter()
+                    .any(|h| h.holding.token_info.address == **mint)
+            })
+            .count();
+        let mut open_positions = valuation.holdings.len() + pending_positions;
+        let mut non_sol_value = valuation.holdings_value() + valuation.committed_value();
+
+        let mut checks = vec![];
+        for trade in trades {
+            let requested = (budget * trade.weight).floor() as u64;
+            let check = |trade, approved: u64, reason: String| {
+                let decision = if approved == 0 {
+                    RiskDecision::Veto
+                } else if approved < requested {
+                    RiskDecision::Resize
+                } else {
+                    RiskDecision::Approve
+                };
+                RiskCheck {
+                    trade,
+                    decision,
+                    requested,
+                    approved,
+                    reason,
+                }
+            };
+
+            if drawdown > self.max_drawdown {
+                let reason = format!(
+                    "drawdown {:.2}% exceeds circuit breaker {:.2}%",
+                    drawdown * 100.0,
+                    self.max_drawdown * 100.0
+                );
+                checks.push(check(trade, 0, reason));
+                continue;
+            }
+
+            let committed = valuation.committed(&trade.token);
+            let held_value = match valuation.holding(&trade.token) {
+                Some(holding) => Some(holding.value() + committed),
+                None => (committed > 0.0).then_some(committed),
+            };
+            if held_value.is_none() && open_positions >= self.max_open_positions {
+                let reason = format!(
+                    "{} open positions reach the limit of {}",
+                    open_positions, self.max_open_positions
+                );
+                checks.push(check(trade, 0, reason));
+                continue;
+            }
+
+            let position_room = self.max_position_share * nav - held_value.unwrap_or_default();
+            let non_sol_room = self.max_non_sol_share * nav - non_sol_value;
+            let room = position_room.min(non_sol_room).max(0.0) as u64;
+
+            let approved = requested.min(room);
+            let reason = if approved == requested {
+                "within limits".to_string()
+            } else if position_room <= non_sol_room {
+                format!(
+                    "position cap of {:.2}% of NAV",
+                    self.max_position_share * 100.0
+                )
+            } else {
+                format!(
+                    "non-SOL cap of {:.2}% of NAV",
+                    self.max_non_sol_share * 100.0
+                )
+            };
+
+            if approved > 0 {
+                non_sol_value += approved as f64;
+                if held_value.is_none() {
+                    open_positions += 1;
+                }
+            }
+
+            checks.push(check(trade, approved, reason));
+        }
+
+        checks
+    }
+}
+
+/// Enforces the [`RiskLimits`] on the trades selected by the strategy before
+/// they reach the portfolio.
+pub struct RiskManager {
+    limits: RiskLimits,
+    state: StoreMap<String, f64, LocalStore>,
+}
+
+impl RiskManager {
+    const RISK_STATE_PREFIX: &'static str = "risk_state";
+    const PEAK_NAV_KEY: &'static str = "peak_nav";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<RiskManager> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            limits: RiskLimits::from_config(Config::get()),
+            state: LocalStore::open_map(trading_prefix(Self::RISK_STATE_PREFIX)),
+        }
+    }
+
+    /// Records the NAV and returns the highest NAV seen so far.
+    fn update_peak_nav(&self, nav: f64) -> Result<f64> {
+        let key = Self::PEAK_NAV_KEY.to_string();
+        let peak_nav = self.state.get(&key)?.unwrap_or_default().max(nav);
+        self.state.insert(key, peak_nav)?;
+
+        Ok(peak_nav)
+    }
+
+    /// Vetoes or resizes the trades, logging every decision.
+    ///
+    /// The weights of the returned trades still refer to `budget`, so resized
+    /// trades no longer sum up to 1.
+    pub fn review(
+        &self,
+        trades: Vec<Trade>,
+        budget: f64,
+        valuation: &PortfolioValuation,
+    ) -> Result<Vec<Trade>> {
+        let peak_nav = self.update_peak_nav(valuation.nav())?;
+
+        let mut approved_trades = vec![];
+        for check in self.limits.assess(trades, budget, valuation, peak_nav) {
+            tracing::info!(
+                "Risk manager {} {}: {} -> {} lamports, {}",
+                check.decision,
+                check.trade.token.symbol,
+                check.requested,
+                check.approved,
+                check.reason
+            );
+
+            PortfolioAction::risk(
+                check.trade.token.address.to_string(),
+                check.decision,
+                check.requested,
+                check.approved,
+                check.reason,
+            )
+            .log();
+
+            if check.approved > 0 {
+                let mut trade = check.trade;
+                trade.weight = check.approved as f64 / budget;
+                approved_trades.push(trade);
+            }
+        }
+
+        Ok(approved_trades)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+    use crate::constant::LAMPORTS_PER_SOL;
+    use crate::portfolio::{HoldingValuation, OtherTokenHolding};
+    use crate::token::structs::TokenInfo;
+    use std::collections::HashMap;
+
+    const SOL: f64 = LAMPORTS_PER_SOL as f64;
+
+    fn token(symbol: &str) -> TokenInfo {
+        TokenInfo {
+            address: Pubkey::new_unique(),
+            decimals: 9,
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            coingecko_id: None,
+        }
+    }
+
+    fn limits() -> RiskLimits {
+        RiskLimits {
+            max_position_share: 0.2,
+            max_non_sol_share: 0.5,
+            max_open_positions: 2,
+            max_drawdown: 0.3,
+        }
+    }
+
+    /// 100 SOL of NAV: `sol` in SOL and the rest in `held`, priced at 1 SOL.
+    fn valuation(sol: f64, held: &[(TokenInfo, f64)]) -> PortfolioValuation {
+        let holdings = held
+            .iter()
+            .map(|(token, amount)| {
+                let mut holding = OtherTokenHolding::init(token.clone());
+                holding.update_buy((amount * SOL) as u64, (amount * SOL) as u64);
+                HoldingValuation {
+                    holding,
+                    price_in_sol: 1.0,
+                }
+            })
+            .collect();
+
+        PortfolioValuation {
+            sol_balance: (sol * SOL) as u64,
+            holdings,
+            sol_price_usd: 100.0,
+            committed: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_position_cap_resizes() {
+        let a = token("A");
+        let valuation = valuation(90.0, &[(a.clone(), 10.0)]);
+        let trades = vec![Trade {
+            token: a,
+            weight: 1.0,
+        }];
+
+        let checks = limits().assess(trades, 50.0 * SOL, &valuation, 0.0);
+        assert_eq!(checks[0].decision, RiskDecision::Resize);
+        assert_eq!(checks[0].approved, (10.0 * SOL) as u64);
+    }
+
+    #[test]
+    fn test_pending_slices_count_as_held() {
+        let (a, b) = (token("A"), token("B"));
+        let mut valuation = valuation(90.0, &[(a.clone(), 10.0)]);
+        // 5 SOL of A and a position in B still to be bought by TWAP slices
+        valuation.committed = HashMap::from([(a.address, (5.0 * SOL) as u64), (b.address, 1)]);
+        let trades = vec![Trade {
+            token: a,
+            weight: 1.0,
+        }];
+
+        let checks = limits().assess(trades, 50.0 * SOL, &valuation, 0.0);
+        assert_eq!(checks[0].decision, RiskDecision::Resize);
+        assert_eq!(checks[0].approved, (5.0 * SOL) as u64);
+
+        let c = token("C");
+        let checks = limits().assess(
+            vec![Trade {
+                token: c,
+                weight: 0.1,
+            }],
+            10.0 * SOL,
+            &valuation,
+            0.0,
+        );
+        // B is a position already
+        assert_eq!(checks[0].decision, RiskDecision::Veto);
+    }
+
+    #[test]
+    fn test_open_positions_limit() {
+        let (a, b, c) = (token("A"), token("B"), token("C"));
+        let valuation = valuation(60.0, &[(a, 20.0), (b.clone(), 20.0)]);
+        let trades = vec![
+            Trade {
+                token: c,
+                weight: 0.5,
+            },
+            Trade {
+                token: b,
+                weight: 0.5,
+            },
+        ];
+
+        let checks = limits().assess(trades, 10.0 * SOL, &valuation, 0.0);
+        // C would be a third position
+        assert_eq!(checks[0].decision, RiskDecision::Veto);
+        // B is at its position cap already
+        assert_eq!(checks[1].decision, RiskDecision::Veto);
+    }
+
+    #[test]
+    fn test_non_sol_cap_resizes() {
+        let mut limits = limits();
+        limits.max_open_positions = 5;
+        let held = [(token("X"), 15.0), (token("Y"), 15.0), (token("Z"), 15.0)];
+        let valuation = valuation(55.0, &held);
+        let trades = vec![Trade {
+            token: token("W"),
+            weight: 1.0,
+        }];
+
+        let checks = limits.assess(trades, 20.0 * SOL, &valuation, 0.0);
+        assert_eq!(checks[0].decision, RiskDecision::Resize);
+        assert_eq!(checks[0].approved, (5.0 * SOL) as u64);
+        assert!(checks[0].reason.contains("non-SOL"));
+    }
+
+    #[test]
+    fn test_drawdown_circuit_breaker() {
+        let valuation = valuation(100.0, &[]);
+        let trades = vec![Trade {
+            token: token("A"),
+            weight: 1.0,
+        }];
+
+        let checks = limits().assess(trades, 10.0 * SOL, &valuation, 200.0 * SOL);
+        assert_eq!(checks[0].decision, RiskDecision::Veto);
+        assert!(checks[0].reason.contains("drawdown"));
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use std::sync::OnceLock;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::utils::get_cur_timestamp;
+use crate::actions::Action;
+use crate::feed::FeedType;
+use crate::fill::SlippageStats;
+use crate::paper::trading_prefix;
+use crate::store::{LocalStore, Record, RecordMap};
+use crate::strategy::{CandidatePerformance, Rejection, Trade};
+
+/// A feed content handed to the LLM and what it extracted from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedInput {
+    pub feed_type: FeedType,
+    /// SHA-256 hash of the feed content
+    pub content_hash: String,
+    pub llm_output: String,
+    pub tokens: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceRecord {
+    pub symbol: String,
+    pub address: String,
+    pub price: f64,
+    /// Currency of the price, `usd` or `sol`
+    pub currency: String,
+}
+
+/// Everything a trading round saw and decided, so every trade can be
+/// explained after the fact.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoundRecord {
+    pub id: u64,
+    /// UNIX timestamp in seconds the round started at
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub feeds: Vec<FeedInput>,
+    pub candidates: Vec<String>,
+    pub performances: Vec<CandidatePerformance>,
+    pub rejections: Vec<Rejection>,
+    /// Trades selected by the strategy, before rebalancing and risk review
+    pub trades: Vec<Trade>,
+    pub prices: Vec<PriceRecord>,
+    /// Portfolio actions logged during the round, with their tx signatures
+    pub actions: Vec<PortfolioAction>,
+    pub error: Option<String>,
+}
+
+impl RoundRecord {
+    pub fn start() -> Result<Self> {
+        Ok(Self {
+            id: RoundRecords::get().records.next_id()?,
+            started_at: get_cur_timestamp(),
+            ..Default::default()
+        })
+    }
+
+    /// Collects the portfolio actions of the round and closes it.
+    pub fn finish(&mut self, error: Option<&anyhow::Error>) {
+        self.finished_at = get_cur_timestamp();
+        self.actions = PortfolioAction::iter()
+            .filter(|(_, raw)| {
+                raw.timestamp() >= self.started_at && raw.timestamp() <= self.finished_at
+            })
+            .map(|(action, _)| action)
+            .collect();
+        self.error = error.map(|e| e.to_string());
+    }
+
+    pub fn tx_signatures(&self) -> Vec<&str> {
+        let mut sigs = vec![];
+        for action in &self.actions {
+            let tx_sig = match action {
+                PortfolioAction::Buy { tx_sig, .. }
+                | PortfolioAction::Sell { tx_sig, .. }
+                | PortfolioAction::Buyback { tx_sig, .. } => tx_sig.as_str(),
+                // The buy of a slice sent in the round is listed already
+                PortfolioAction::TwapSlice {
+                    tx_sig: Some(tx_sig),
+                    ..
+                } => tx_sig.as_str(),
+                _ => continue,
+            };
+            if !sigs.contains(&tx_sig) {
+                sigs.push(tx_sig);
+            }
+        }
+        sigs
+    }
+
+    /// Slippage and fees of the swaps of the round whose fill is known.
+    pub fn slippage(&self) -> SlippageStats {
+        SlippageStats::from_fills(self.actions.iter().filter_map(|action| action.fill()))
+    }
+}
+
+impl std::fmt::Display for RoundRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Round {}: {} candidates, {} rejected, {} trades, {} txs",
+            self.id,
+            self.candidates.len(),
+            self.rejections.len(),
+            self.trades.len(),
+            self.tx_signatures().len()
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, ", failed: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Record for RoundRecord {
+    fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// The [`RoundRecord`]s, stored as JSON so they survive schema changes.
+pub struct RoundRecords {
+    records: RecordMap<RoundRecord, LocalStore>,
+}
+
+impl RoundRecords {
+    const ROUND_RECORDS_PREFIX: &'static str = "round_records";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<RoundRecords> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            records: RecordMap::new(trading_prefix(Self::ROUND_RECORDS_PREFIX)),
+        }
+    }
+
+    pub fn insert(&self, record: &RoundRecord) -> Result<()> {
+        self.records.save(record)
+    }
+
+    pub fn record(&self, id: u64) -> Result<Option<RoundRecord>> {
+        self.records.get(id)
+    }
+
+    /// Attaches an action of the round `id` logged after it was recorded,
+    /// such as a later TWAP slice. A round not recorded yet collects it on
+    /// its own when it finishes.
+    pub fn attach(&self, id: u64, action: PortfolioAction) -> Result<()> {
+        let Some(mut record) = self.record(id)? else {
+            return Ok(());
+        };
+        record.actions.push(action);
+        self.insert(&record)
+    }
+
+    /// All records, oldest first.
+    pub fn list(&self) -> Vec<RoundRecord> {
+        self.records.list()
+    }
+}
+
+/// Lists the round records, or prints the record of `id` in full.
+pub fn inspect(id: Option<String>) -> Result<()> {
+    let records = RoundRecords::get();
+    match id {
+        Some(id) => {
+            let id = id.parse()?;
+            let record = records
+                .record(id)?
+                .ok_or(anyhow::anyhow!("Round {} not found", id))?;
+            tracing::info!("{}", serde_json::to_string_pretty(&record)?);
+        }
+        None => {
+            for record in records.list() {
+                tracing::info!("{}", record);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_json_is_forward_compatible() {
+        let record: RoundRecord = serde_json::from_str(r#"{"id": 42, "candidates": ["BONK"]}"#)
+            .expect("missing fields must default");
+        assert_eq!(record.id, 42);
+        assert_eq!(record.candidates, vec!["BONK"]);
+        assert!(record.actions.is_empty());
+    }
+
+    #[test]
+    fn test_rounds_of_the_same_second() {
+        std::env::set_var("STORE_PATH", "/tmp/test_round_store");
+        dotenv::from_filename(".env.example").ok();
+
+        let first = RoundRecord::start().unwrap();
+        let second = RoundRecord::start().unwrap();
+        assert!(second.id > first.id);
+    }
+
+    #[test]
+    fn test_later_slices_attach_to_their_round() {
+        std::env::set_var("STORE_PATH", "/tmp/test_round_store");
+        dotenv::from_filename(".env.example").ok();
+
+        let records = RoundRecords::get();
+        let mut record = RoundRecord::start().unwrap();
+        let slice = |slice: usize, tx_sig: &str| {
+            PortfolioAction::twap_slice(
+                "A".to_string(),
+                0,
+                slice,
+                1,
+                Some(tx_sig.to_string()),
+                None,
+                Some(record.id),
+            )
+        };
+        // The first slice is sent within the round
+        record.actions = vec![
+            PortfolioAction::buy("A".to_string(), 1, 1, "sig0".to_string()),
+            slice(0, "sig0"),
+        ];
+        records.insert(&record).unwrap();
+
+        records.attach(record.id, slice(1, "sig1")).unwrap();
+        let record = records.record(record.id).unwrap().unwrap();
+        assert_eq!(record.tx_signatures(), vec!["sig0", "sig1"]);
+    }
+}
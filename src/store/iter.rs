@@ -40,7 +40,20 @@ impl<
     type Item = (Cow<'a, K>, Cow<'a, V>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((key, value)) = self.iter.next() {
+        // The underlying iterator may start before the keys of the map and run
+        // past them, skip to the first key of the map and stop after its last
+        let mut next = self.iter.next();
+        while let Some((key, _)) = &next {
+            if key.starts_with(&self.prefix) {
+                break;
+            }
+            if key.as_ref() > self.prefix.as_slice() {
+                return None;
+            }
+            next = self.iter.next();
+        }
+
+        if let Some((key, value)) = next {
             // Deserialize the key and value.
             let key = bincode::deserialize(&key[self.prefix.len()..])
                 .map_err(|e| {
@@ -59,3 +72,39 @@ impl<
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(prefix: &str, key: u8, value: u8) -> (Box<[u8]>, Box<[u8]>) {
+        let key = [prefix.as_bytes(), &bincode::serialize(&key).unwrap()].concat();
+        let value = bincode::serialize(&value).unwrap();
+        (key.into_boxed_slice(), value.into_boxed_slice())
+    }
+
+    #[test]
+    fn test_map_iter_stays_in_prefix() {
+        let entries = vec![
+            entry("a", 1, 10),
+            entry("b", 1, 20),
+            entry("b", 2, 30),
+            entry("c", 1, 40),
+        ];
+
+        let iter: MapIter<u8, u8, _> = MapIter::new(b"b", entries.into_iter());
+        let items = iter
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec![(1, 20), (2, 30)]);
+    }
+
+    #[test]
+    fn test_map_iter_of_empty_map() {
+        // A prefix iterator seeks to the prefix and runs to the end of the store
+        let entries = vec![entry("c", 1, 40), entry("d", 1, 50)];
+
+        let mut iter: MapIter<u8, u8, _> = MapIter::new(b"b", entries.into_iter());
+        assert!(iter.next().is_none());
+    }
+}
@@ -45,4 +45,28 @@ impl<K: Serialize + DeserializeOwned, V: Serialize + DeserializeOwned, S: Store>
         let iter = S::iter(self.prefix.as_ref());
         MapIter::new(&self.prefix, iter)
     }
+
+    /// Moves the entries out of the map, such as a legacy map migrated to new
+    /// keys, each removed once `move_entry` stored it. Returns the number of
+    /// entries moved.
+    pub fn drain_into(
+        &self,
+        mut move_entry: impl FnMut(&K, V) -> anyhow::Result<()>,
+    ) -> anyhow::Result<usize>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let entries = self
+            .iter()
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect::<Vec<_>>();
+        let count = entries.len();
+        for (key, value) in entries {
+            move_entry(&key, value)?;
+            self.remove(&key)?;
+        }
+
+        Ok(count)
+    }
 }
@@ -2,10 +2,14 @@ pub mod iter;
 #[cfg(feature = "levels")]
 pub mod leveldb;
 pub mod map;
+pub mod records;
 #[cfg(feature = "rocks")]
 pub mod rocksdb;
+pub mod sequence;
 
 pub use map::StoreMap;
+pub use records::{Record, RecordMap};
+pub use sequence::IdSequence;
 use serde::{de::DeserializeOwned, Serialize};
 
 /// TODO: define error
@@ -31,6 +35,10 @@ pub trait Store {
     }
 }
 
+/// The prefix of the [`IdSequence`] counters, keyed by the prefix of the map
+/// they number.
+pub const NEXT_IDS_PREFIX: &str = "next_ids";
+
 #[cfg(feature = "levels")]
 pub type LocalStore = crate::store::leveldb::LevelDB;
 
@@ -0,0 +1,105 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use std::marker::PhantomData;
+
+use super::{IdSequence, Store, StoreMap, NEXT_IDS_PREFIX};
+
+/// A record numbered by a [`RecordMap`].
+pub trait Record: Serialize + DeserializeOwned {
+    fn id(&self) -> u64;
+}
+
+/// Records stored as JSON under their big-endian id, so that they iterate
+/// oldest first, and numbered from an [`IdSequence`].
+pub struct RecordMap<T: Record, S: Store> {
+    records: StoreMap<[u8; 8], String, S>,
+    ids: IdSequence<S>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Record, S: Store> RecordMap<T, S> {
+    pub fn new(prefix: impl AsRef<str>) -> Self {
+        Self {
+            records: StoreMap::new(prefix.as_ref()),
+            ids: IdSequence::new(NEXT_IDS_PREFIX, prefix.as_ref()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Takes the id of a new record, following the last record stored before
+    /// the counter existed.
+    pub fn next_id(&self) -> anyhow::Result<u64> {
+        self.ids.next(|| {
+            self.list()
+                .last()
+                .map(|record| record.id() + 1)
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn save(&self, record: &T) -> anyhow::Result<()> {
+        self.records
+            .insert(record.id().to_be_bytes(), serde_json::to_string(record)?)
+    }
+
+    pub fn get(&self, id: u64) -> anyhow::Result<Option<T>> {
+        match self.records.get(&id.to_be_bytes())? {
+            Some(raw) => Ok(Some(serde_json::from_str(&raw)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// All records, oldest first, skipping those that no longer deserialize.
+    pub fn list(&self) -> Vec<T> {
+        self.records
+            .iter()
+            .filter_map(|(_, raw)| serde_json::from_str(&raw).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::LocalStore;
+    use serde::Deserialize;
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Note {
+        id: u64,
+        text: String,
+    }
+
+    impl Record for Note {
+        fn id(&self) -> u64 {
+            self.id
+        }
+    }
+
+    #[test]
+    fn test_record_map() {
+        std::env::set_var("STORE_PATH", "/tmp/test_records_store");
+        dotenv::from_filename(".env.example").ok();
+
+        let prefix = Keypair::new().pubkey().to_string();
+        let notes = RecordMap::<Note, LocalStore>::new(&prefix);
+        for text in ["a", "b"] {
+            let note = Note {
+                id: notes.next_id().unwrap(),
+                text: text.to_string(),
+            };
+            notes.save(&note).unwrap();
+        }
+        assert_eq!(notes.list().len(), 2);
+        assert_eq!(notes.get(1).unwrap().unwrap().text, "b");
+
+        // An existing map without a counter is numbered after its last record
+        let legacy = Keypair::new().pubkey().to_string();
+        StoreMap::<[u8; 8], String, LocalStore>::new(&legacy)
+            .insert(7u64.to_be_bytes(), r#"{"id":7,"text":"c"}"#.to_string())
+            .unwrap();
+        let notes = RecordMap::<Note, LocalStore>::new(&legacy);
+        assert_eq!(notes.next_id().unwrap(), 8);
+    }
+}
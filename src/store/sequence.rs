@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+
+use super::{Store, StoreMap};
+
+/// A persisted counter handing out the ids of the records of a map, so that
+/// the next id is neither found by scanning the records nor handed out twice.
+pub struct IdSequence<S: Store> {
+    name: String,
+    next_ids: StoreMap<String, u64, S>,
+    lock: Mutex<()>,
+}
+
+impl<S: Store> IdSequence<S> {
+    /// The counters are kept under `prefix`, which must not be the prefix of
+    /// the map they number.
+    pub fn new(prefix: impl AsRef<str>, name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            next_ids: StoreMap::new(prefix),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Takes the next id. `first` seeds a counter not stored yet, for the
+    /// records written before it existed.
+    pub fn next(&self, first: impl FnOnce() -> u64) -> anyhow::Result<u64> {
+        let _guard = self.lock.lock().unwrap();
+        let id = match self.next_ids.get(&self.name)? {
+            Some(id) => id,
+            None => first(),
+        };
+        self.next_ids.insert(self.name.clone(), id + 1)?;
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::LocalStore;
+    use solana_sdk::signer::{keypair::Keypair, Signer};
+
+    #[test]
+    fn test_id_sequence() {
+        std::env::set_var("STORE_PATH", "/tmp/test_sequence_store");
+        dotenv::from_filename(".env.example").ok();
+
+        let name = Keypair::new().pubkey().to_string();
+        let ids = IdSequence::<LocalStore>::new("test_next_ids", &name);
+        assert_eq!(ids.next(|| 5).unwrap(), 5);
+        assert_eq!(ids.next(|| 0).unwrap(), 6);
+
+        // Persisted, not held by the instance
+        let ids = IdSequence::<LocalStore>::new("test_next_ids", &name);
+        assert_eq!(ids.next(|| 0).unwrap(), 7);
+    }
+}
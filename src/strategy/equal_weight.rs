@@ -0,0 +1,36 @@
+use anyhow::Result;
+
+use super::{ranked_performances, CandidateData, Strategy, Trade};
+
+/// Splits the budget evenly over the best performing candidates.
+pub struct EqualWeightStrategy {
+    max_picks: usize,
+}
+
+impl EqualWeightStrategy {
+    pub fn new(max_picks: usize) -> Self {
+        Self { max_picks }
+    }
+}
+
+impl Strategy for EqualWeightStrategy {
+    fn name(&self) -> &'static str {
+        "equal_weight"
+    }
+
+    fn select(&self, candidates: &[CandidateData]) -> Result<Vec<Trade>> {
+        let performances = ranked_performances(candidates);
+
+        let picks = performances.len().min(self.max_picks);
+        let trades = performances
+            .into_iter()
+            .take(picks)
+            .map(|performance| Trade {
+                token: performance.token,
+                weight: 1.0 / picks as f64,
+            })
+            .collect();
+
+        Ok(trades)
+    }
+}
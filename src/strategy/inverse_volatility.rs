@@ -0,0 +1,52 @@
+use anyhow::Result;
+
+use super::{ranked_performances, CandidateData, Strategy, Trade};
+use crate::indicators::realized_volatility;
+
+/// Picks the best performing candidates and weights them by the inverse of
+/// their volatility, so calmer tokens get a larger share of the budget.
+pub struct InverseVolatilityStrategy {
+    max_picks: usize,
+}
+
+impl InverseVolatilityStrategy {
+    pub fn new(max_picks: usize) -> Self {
+        Self { max_picks }
+    }
+}
+
+impl Strategy for InverseVolatilityStrategy {
+    fn name(&self) -> &'static str {
+        "inverse_volatility"
+    }
+
+    fn select(&self, candidates: &[CandidateData]) -> Result<Vec<Trade>> {
+        let performances = ranked_performances(candidates);
+
+        let picks = performances
+            .into_iter()
+            .take(self.max_picks)
+            .filter_map(|performance| {
+                let candidate = candidates.iter().find(|c| c.token == performance.token)?;
+                let volatility = realized_volatility(&candidate.prices());
+                if volatility > 0.0 {
+                    Some((performance.token, 1.0 / volatility))
+                } else {
+                    tracing::warn!("No volatility for {}, ignore it", performance.token.symbol);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let total: f64 = picks.iter().map(|(_, inverse)| inverse).sum();
+        let trades = picks
+            .into_iter()
+            .map(|(token, inverse)| Trade {
+                token,
+                weight: inverse / total,
+            })
+            .collect();
+
+        Ok(trades)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::config::Config;
+use crate::jupiter::quote::Quote;
+
+use super::CandidateData;
+
+/// Rejects candidates too illiquid to be traded.
+///
+/// A threshold of 0 disables the check.
+#[derive(Debug, Clone)]
+pub struct LiquidityFilter {
+    /// Minimum 24h trading volume in USD
+    pub min_volume_usd: f64,
+    /// Minimum market cap in USD
+    pub min_market_cap_usd: f64,
+    /// Maximum price impact of buying the intended size, as a fraction
+    pub max_price_impact: f64,
+}
+
+impl LiquidityFilter {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            min_volume_usd: config.min_volume_usd,
+            min_market_cap_usd: config.min_market_cap_usd,
+            max_price_impact: config.max_price_impact,
+        }
+    }
+
+    /// Checks the latest 24h volume and market cap of the candidate, returning
+    /// the reason of the rejection if any.
+    pub fn check_market(&self, candidate: &CandidateData) -> Result<(), String> {
+        let latest = |series: &[Vec<f64>]| series.last().map(|v| v[1]).unwrap_or_default();
+
+        let volume = latest(&candidate.history.total_volumes);
+        if volume < self.min_volume_usd {
+            return Err(format!(
+                "24h volume ${:.0} below ${:.0}",
+                volume, self.min_volume_usd
+            ));
+        }
+
+        let market_cap = latest(&candidate.history.market_caps);
+        if market_cap < self.min_market_cap_usd {
+            return Err(format!(
+                "market cap ${:.0} below ${:.0}",
+                market_cap, self.min_market_cap_usd
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the price impact of a quote for the intended size.
+    pub fn check_quote(&self, quote: &Quote) -> Result<(), String> {
+        if self.max_price_impact > 0.0 && quote.price_impact > self.max_price_impact {
+            return Err(format!(
+                "price impact {:.2}% of {} lamports above {:.2}%",
+                quote.price_impact * 100.0,
+                quote.in_amount,
+                self.max_price_impact * 100.0
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::tests::candidate;
+
+    fn filter() -> LiquidityFilter {
+        LiquidityFilter {
+            min_volume_usd: 100_000.0,
+            min_market_cap_usd: 1_000_000.0,
+            max_price_impact: 0.02,
+        }
+    }
+
+    fn with_market(volume: f64, market_cap: f64) -> CandidateData {
+        let mut candidate = candidate("A", &[1.0, 1.0]);
+        candidate.history.market_caps = vec![vec![0.0, market_cap]];
+        candidate.history.total_volumes = vec![vec![0.0, volume]];
+        candidate
+    }
+
+    #[test]
+    fn test_market_filter() {
+        let filter = filter();
+
+        assert!(filter
+            .check_market(&with_market(500_000.0, 5_000_000.0))
+            .is_ok());
+        assert!(filter
+            .check_market(&with_market(50_000.0, 5_000_000.0))
+            .unwrap_err()
+            .contains("volume"));
+        assert!(filter
+            .check_market(&with_market(500_000.0, 500_000.0))
+            .unwrap_err()
+            .contains("market cap"));
+    }
+
+    #[test]
+    fn test_quote_filter() {
+        let quote = |price_impact| Quote {
+            in_amount: 1_000_000_000,
+            out_amount: 1_000,
+            price_impact,
+            response: serde_json::Value::Null,
+        };
+
+        assert!(filter().check_quote(&quote(0.01)).is_ok());
+        assert!(filter().check_quote(&quote(0.05)).is_err());
+    }
+}
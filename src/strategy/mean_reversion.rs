@@ -0,0 +1,64 @@
+use anyhow::Result;
+
+use std::cmp::Ordering;
+
+use super::{CandidateData, Strategy, Trade};
+use crate::indicators::bollinger;
+
+/// Buys the candidates trading furthest below their mean price over the
+/// lookback period, expecting them to revert to it.
+///
+/// Candidates are scored by the z-score of the latest price; only those below
+/// their mean are picked, and they are weighted evenly.
+pub struct MeanReversionStrategy {
+    max_picks: usize,
+}
+
+impl MeanReversionStrategy {
+    pub fn new(max_picks: usize) -> Self {
+        Self { max_picks }
+    }
+}
+
+fn z_score(prices: &[f64]) -> Option<f64> {
+    let last = *prices.last()?;
+    // A band of one standard deviation over the whole period
+    let band = *bollinger(prices, prices.len(), 1.0).first()?;
+    let std_dev = band.upper - band.middle;
+    if std_dev == 0.0 {
+        return None;
+    }
+
+    Some((last - band.middle) / std_dev)
+}
+
+impl Strategy for MeanReversionStrategy {
+    fn name(&self) -> &'static str {
+        "mean_reversion"
+    }
+
+    fn select(&self, candidates: &[CandidateData]) -> Result<Vec<Trade>> {
+        let mut scored = candidates
+            .iter()
+            .filter_map(|c| z_score(&c.prices()).map(|z| (c, z)))
+            .filter(|(_, z)| *z < 0.0)
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+        for (candidate, z) in &scored {
+            tracing::info!("{:<10}: z-score: {:>6.2}", candidate.token.symbol, z);
+        }
+
+        let picks = scored.len().min(self.max_picks);
+        let trades = scored
+            .into_iter()
+            .take(picks)
+            .map(|(candidate, _)| Trade {
+                token: candidate.token.clone(),
+                weight: 1.0 / picks as f64,
+            })
+            .collect();
+
+        Ok(trades)
+    }
+}
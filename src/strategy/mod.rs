@@ -0,0 +1,493 @@
+mod equal_weight;
+mod inverse_volatility;
+mod liquidity;
+mod mean_reversion;
+mod momentum;
+mod sizing;
+mod trend;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::constant::*;
+use crate::indicators::closes;
+use crate::jupiter::quote::quote;
+use crate::jupiter::SOL_MINT;
+use crate::price::coingecko::HistoricalPriceResponse;
+use crate::price::{PriceAggregator, PriceId, PriceProvider};
+use crate::token::store::SolanaTokenStore;
+use crate::token::structs::TokenInfo;
+
+pub use equal_weight::EqualWeightStrategy;
+pub use inverse_volatility::InverseVolatilityStrategy;
+pub use liquidity::LiquidityFilter;
+pub use mean_reversion::MeanReversionStrategy;
+pub use momentum::MomentumStrategy;
+pub use sizing::{Sizing, SizingMode};
+pub use trend::TrendStrategy;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub token: TokenInfo,
+    // weight of the token in the portfolio
+    // from 0 to 1
+    pub weight: f64,
+}
+
+impl std::fmt::Display for Trade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Trade: {}, weight: {}", self.token, self.weight)
+    }
+}
+
+/// Market data of a candidate token handed to a [`Strategy`].
+#[derive(Debug)]
+pub struct CandidateData {
+    pub token: TokenInfo,
+    pub history: HistoricalPriceResponse,
+}
+
+impl CandidateData {
+    /// Prices of the history without timestamps, oldest first.
+    pub fn prices(&self) -> Vec<f64> {
+        closes(&self.history.prices)
+    }
+
+    pub fn performance(&self) -> CandidatePerformance {
+        CandidatePerformance {
+            token: self.token.clone(),
+            hold_profit_rate: calculate_hold_profit_rate(&self.history.prices),
+            max_profit_rate: calculate_max_profit_rate(&self.history.prices),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidatePerformance {
+    pub token: TokenInfo,
+    pub hold_profit_rate: f64,
+    pub max_profit_rate: f64,
+}
+
+impl CandidatePerformance {
+    pub fn profit_rate(&self) -> f64 {
+        self.hold_profit_rate
+    }
+}
+
+/// Compares two CandidatePerformance instances.
+///
+/// This function compares primarily by max_profit_rate in descending order,
+/// and in case of a tie, by hold_profit_rate in descending order.
+fn compare_candidate_performance(a: &CandidatePerformance, b: &CandidatePerformance) -> Ordering {
+    b.profit_rate()
+        .partial_cmp(&a.profit_rate())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Computes the performances of the candidates sorted from best to worst.
+fn ranked_performances(candidates: &[CandidateData]) -> Vec<CandidatePerformance> {
+    let mut performances: Vec<CandidatePerformance> =
+        candidates.iter().map(|c| c.performance()).collect();
+
+    for performance in &performances {
+        tracing::info!(
+            "{:<10}: hold_profit_rate: {:>6.2}%, max_profit_rate: {:>6.2}%",
+            performance.token.symbol,
+            performance.hold_profit_rate * 100.0, // Convert to percentage
+            performance.max_profit_rate * 100.0   // Convert to percentage
+        );
+    }
+
+    performances.sort_by(compare_candidate_performance);
+    performances
+}
+
+/// A trading strategy turns the candidate tokens and their market data into
+/// the trades of a round.
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn select(&self, candidates: &[CandidateData]) -> Result<Vec<Trade>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrategyKind {
+    Momentum,
+    EqualWeight,
+    MeanReversion,
+    InverseVolatility,
+    Trend,
+}
+
+impl StrategyKind {
+    pub fn build(&self, max_picks: usize) -> Box<dyn Strategy> {
+        match self {
+            StrategyKind::Momentum => Box::new(MomentumStrategy::new()),
+            StrategyKind::EqualWeight => Box::new(EqualWeightStrategy::new(max_picks)),
+            StrategyKind::MeanReversion => Box::new(MeanReversionStrategy::new(max_picks)),
+            StrategyKind::InverseVolatility => Box::new(InverseVolatilityStrategy::new(max_picks)),
+            StrategyKind::Trend => Box::new(TrendStrategy::new(max_picks)),
+        }
+    }
+}
+
+impl FromStr for StrategyKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "momentum" => Ok(StrategyKind::Momentum),
+            "equal_weight" => Ok(StrategyKind::EqualWeight),
+            "mean_reversion" => Ok(StrategyKind::MeanReversion),
+            "inverse_volatility" => Ok(StrategyKind::InverseVolatility),
+            "trend" => Ok(StrategyKind::Trend),
+            _ => Err(anyhow::anyhow!("Unknown strategy: {}", s)),
+        }
+    }
+}
+
+/// Why a candidate was left out of the trades.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rejection {
+    pub candidate: String,
+    pub reason: String,
+}
+
+impl Rejection {
+    fn new(candidate: impl Into<String>, reason: impl Into<String>) -> Self {
+        let rejection = Self {
+            candidate: candidate.into(),
+            reason: reason.into(),
+        };
+        tracing::warn!("Rejected {}: {}", rejection.candidate, rejection.reason);
+        rejection
+    }
+}
+
+/// Outcome of [`select_tokens`] with the data it was decided on.
+#[derive(Debug, Default)]
+pub struct Selection {
+    pub performances: Vec<CandidatePerformance>,
+    /// Latest USD price of every candidate with market data
+    pub prices: Vec<(TokenInfo, f64)>,
+    pub rejections: Vec<Rejection>,
+    pub trades: Vec<Trade>,
+}
+
+pub async fn select_tokens(candidates: HashSet<String>) -> Result<Selection> {
+    tracing::info!("Start selecting tokens...");
+
+    let config = Config::get();
+    let price_provider = PriceAggregator::get();
+    let mut selection = Selection::default();
+
+    let mut candidate_tokens = Vec::new();
+    let token_store = SolanaTokenStore::get();
+    for candidate in candidates {
+        if let Some(token_info) = token_store.get_token_info(&candidate).await? {
+            if token_info.coingecko_id.is_some() {
+                candidate_tokens.push(token_info);
+            } else {
+                selection
+                    .rejections
+                    .push(Rejection::new(candidate, "no coingecko id"));
+            }
+        } else {
+            selection
+                .rejections
+                .push(Rejection::new(candidate, "token not found"));
+            continue;
+        }
+    }
+
+    let mut candidate_data = Vec::new();
+    for token_info in candidate_tokens {
+        let history = if let Ok(price) = price_provider
+            .history(
+                &PriceId::from(&token_info),
+                USD_CURRENCY,
+                config.strategy_lookback_days,
+            )
+            .await
+        {
+            price
+        } else {
+            selection.rejections.push(Rejection::new(
+                token_info.symbol,
+                "failed to get historical price",
+            ));
+            continue;
+        };
+
+        candidate_data.push(CandidateData {
+            token: token_info,
+            history,
+        });
+    }
+
+    for candidate in &candidate_data {
+        selection.performances.push(candidate.performance());
+        if let Some(&price) = candidate.prices().last() {
+            selection.prices.push((candidate.token.clone(), price));
+        }
+    }
+
+    // Reject illiquid candidates before the strategy sees them
+    let liquidity_filter = LiquidityFilter::from_config(config);
+    candidate_data.retain(|candidate| match liquidity_filter.check_market(candidate) {
+        Ok(()) => true,
+        Err(reason) => {
+            selection
+                .rejections
+                .push(Rejection::new(candidate.token.symbol.clone(), reason));
+            false
+        }
+    });
+
+    let strategy = config.strategy.build(config.strategy_max_picks);
+    tracing::info!("Selecting tokens with {} strategy", strategy.name());
+    let selected = strategy.select(&candidate_data)?;
+    let sizing = Sizing::from_config(config);
+    tracing::info!("Sizing picks with {:?} mode", sizing.mode);
+
+    // Reject trades whose intended size moves the price too much, sizing the
+    // picks left again after a rejection so no share of the budget sits idle
+    let budget = LAMPORTS_PER_SOL as f64 * config.max_sol_trading_amount_one_day;
+    let mut picks = selected;
+    loop {
+        let sized = sizing.size(picks.clone(), &candidate_data);
+        let mut rejected = vec![];
+        for trade in &sized {
+            let sol_amount = (budget * trade.weight).floor() as u64;
+            let checked = quote(
+                SOL_MINT,
+                &trade.token.address.to_string(),
+                sol_amount,
+                config.slippage_max_bps,
+            )
+            .await
+            .map_err(|e| format!("failed to quote: {}", e))
+            .and_then(|quote| liquidity_filter.check_quote(&quote));
+            if let Err(reason) = checked {
+                selection
+                    .rejections
+                    .push(Rejection::new(trade.token.symbol.clone(), reason));
+                rejected.push(trade.token.address);
+            }
+        }
+
+        if rejected.is_empty() {
+            selection.trades = sized;
+            break;
+        }
+        picks.retain(|pick| !rejected.contains(&pick.token.address));
+    }
+
+    for trade in &selection.trades {
+        tracing::info!("{}", trade);
+    }
+
+    Ok(selection)
+}
+
+/// Calculates the profit rate from holding a token over a specified period.
+///
+/// This function simulates buying the token at the beginning of the period
+/// and selling it at the end. The profit rate is calculated as a percentage
+/// based on the difference between the selling price and the buying price.
+///
+/// # Arguments
+///
+/// * `prices`: A 2D vector where each sub-vector contains:
+///   - `timestamp`: The time at which the price was recorded (in UNIX timestamp).
+///   - `price`: The price of the token at that timestamp.
+///
+/// # Returns
+///
+/// Returns the profit rate as a floating-point number representing the
+/// percentage change in price. The calculation is done using the formula:
+///
+///     Profit Rate = (Sell Price - Buy Price) / Buy Price
+///
+/// If the input is empty, the function returns 0.0.
+fn calculate_hold_profit_rate(prices: &[Vec<f64>]) -> f64 {
+    if prices.is_empty() {
+        return 0.0;
+    }
+
+    let buy_price = prices[0][1];
+    let sell_price = prices[prices.len() - 1][1];
+
+    (sell_price - buy_price) / buy_price
+}
+
+/// Calculates the maximum profit rate that can be achieved from holding a token
+/// over a specified period based on historical price data.
+///
+/// This function simulates buying the token at the lowest price within the
+/// given period and selling it at the highest price. The goal is to determine
+/// the best possible profit that could have been made by strategically
+/// choosing the buy and sell points.
+///
+/// # Arguments
+///
+/// * `prices`: A 2D vector where each sub-vector contains:
+///   - `timestamp`: The time at which the price was recorded (in UNIX timestamp).
+///   - `price`: The price of the token at that timestamp.
+///
+/// # Returns
+///
+/// Returns the maximum profit rate as a floating-point number representing the
+/// percentage change in price. The calculation is done using the formula:
+///
+///     Profit Rate = ((Sell Price - Buy Price) / Buy Price) * 100
+///
+/// If no profit can be made (e.g., if prices are always decreasing), the function will return 0.0.
+fn calculate_max_profit_rate(prices: &[Vec<f64>]) -> f64 {
+    if prices.is_empty() {
+        return 0.0;
+    }
+
+    let mut min_price = f64::MAX;
+    let mut max_profit_rate = 0.0;
+
+    for price in prices.iter().map(|p| p[1]) {
+        if price < min_price {
+            min_price = price;
+        } else {
+            let profit_rate = (price - min_price) / min_price;
+            if profit_rate > max_profit_rate {
+                max_profit_rate = profit_rate;
+            }
+        }
+    }
+
+    max_profit_rate
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+
+    pub fn candidate(symbol: &str, prices: &[f64]) -> CandidateData {
+        CandidateData {
+            token: TokenInfo {
+                address: Pubkey::new_unique(),
+                decimals: 6,
+                name: symbol.to_string(),
+                symbol: symbol.to_string(),
+                coingecko_id: Some(symbol.to_lowercase()),
+            },
+            history: HistoricalPriceResponse {
+                prices: prices
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| vec![i as f64 * 3_600_000.0, *p])
+                    .collect(),
+                market_caps: vec![],
+                total_volumes: vec![],
+            },
+        }
+    }
+
+    fn symbols(trades: &[Trade]) -> Vec<&str> {
+        trades.iter().map(|t| t.token.symbol.as_str()).collect()
+    }
+
+    #[test]
+    fn test_strategy_kind_from_str() {
+        assert_eq!(
+            "momentum".parse::<StrategyKind>().unwrap(),
+            StrategyKind::Momentum
+        );
+        assert_eq!(
+            "Inverse-Volatility".parse::<StrategyKind>().unwrap(),
+            StrategyKind::InverseVolatility
+        );
+        assert!("buy_everything".parse::<StrategyKind>().is_err());
+    }
+
+    #[test]
+    fn test_momentum_picks_top_performers() {
+        let candidates = vec![
+            candidate("AAA", &[1.0, 1.1]),
+            candidate("BBB", &[1.0, 1.5]),
+            candidate("CCC", &[1.0, 0.9]),
+            candidate("DDD", &[1.0, 1.3]),
+        ];
+
+        let trades = MomentumStrategy::new().select(&candidates).unwrap();
+        assert_eq!(symbols(&trades), vec!["BBB", "DDD", "AAA"]);
+        assert_eq!(trades[0].weight, 0.5);
+
+        let trades = MomentumStrategy::new().select(&candidates[..2]).unwrap();
+        assert_eq!(trades[0].weight, 0.625);
+        assert_eq!(trades[1].weight, 0.375);
+    }
+
+    #[test]
+    fn test_equal_weight() {
+        let candidates = vec![
+            candidate("AAA", &[1.0, 1.1]),
+            candidate("BBB", &[1.0, 1.5]),
+            candidate("CCC", &[1.0, 0.9]),
+        ];
+
+        let trades = EqualWeightStrategy::new(2).select(&candidates).unwrap();
+        assert_eq!(symbols(&trades), vec!["BBB", "AAA"]);
+        assert!(trades.iter().all(|t| t.weight == 0.5));
+    }
+
+    #[test]
+    fn test_mean_reversion_picks_oversold() {
+        let candidates = vec![
+            candidate("UP", &[1.0, 1.0, 1.0, 1.2]),
+            candidate("DOWN", &[1.0, 1.0, 1.0, 0.8]),
+        ];
+
+        let trades = MeanReversionStrategy::new(3).select(&candidates).unwrap();
+        assert_eq!(symbols(&trades), vec!["DOWN"]);
+        assert_eq!(trades[0].weight, 1.0);
+    }
+
+    #[test]
+    fn test_trend_skips_overbought() {
+        // An accelerating uptrend with pullbacks, a steady pump and a flat line
+        let trend = (0..60)
+            .map(|i| 10.0 + 0.002 * (i * i) as f64 + if i % 2 == 0 { -0.3 } else { 0.3 })
+            .collect::<Vec<_>>();
+        let pump = (0..60).map(|i| 10.0 + i as f64).collect::<Vec<_>>();
+        let candidates = vec![
+            candidate("TREND", &trend),
+            candidate("PUMP", &pump),
+            candidate("FLAT", &[10.0; 60]),
+        ];
+
+        let trades = TrendStrategy::new(3).select(&candidates).unwrap();
+        assert_eq!(symbols(&trades), vec!["TREND"]);
+    }
+
+    #[test]
+    fn test_inverse_volatility_weights() {
+        let candidates = vec![
+            candidate("CALM", &[1.0, 1.01, 1.02, 1.03]),
+            candidate("WILD", &[1.0, 1.2, 0.9, 1.3]),
+        ];
+
+        let trades = InverseVolatilityStrategy::new(3)
+            .select(&candidates)
+            .unwrap();
+        let calm = trades.iter().find(|t| t.token.symbol == "CALM").unwrap();
+        let wild = trades.iter().find(|t| t.token.symbol == "WILD").unwrap();
+        assert!(calm.weight > wild.weight);
+        assert!((calm.weight + wild.weight - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+
+use super::{ranked_performances, CandidateData, Strategy, Trade};
+
+/// Buys the best performing candidates over the lookback period.
+///
+/// The top candidates by hold profit rate are weighted 5/3/2, normalized over
+/// the picks when there are fewer than three.
+pub struct MomentumStrategy {
+    weights: Vec<u32>,
+}
+
+impl MomentumStrategy {
+    pub fn new() -> Self {
+        Self {
+            weights: vec![5, 3, 2],
+        }
+    }
+}
+
+impl Strategy for MomentumStrategy {
+    fn name(&self) -> &'static str {
+        "momentum"
+    }
+
+    fn select(&self, candidates: &[CandidateData]) -> Result<Vec<Trade>> {
+        let performances = ranked_performances(candidates);
+
+        // Normalize the weights over the actual number of picks
+        let picks = performances.len().min(self.weights.len());
+        let total_parts: u32 = self.weights[..picks].iter().sum();
+
+        let mut trades = vec![];
+        for (i, performance) in performances.into_iter().take(picks).enumerate() {
+            let weight = self.weights[i] as f64 / total_parts as f64;
+            trades.push(Trade {
+                token: performance.token,
+                weight,
+            });
+        }
+
+        Ok(trades)
+    }
+}
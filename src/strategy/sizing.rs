@@ -0,0 +1,345 @@
+use serde::{Deserialize, Serialize};
+
+use std::str::FromStr;
+
+use super::{CandidateData, Trade};
+use crate::config::Config;
+use crate::indicators::realized_volatility;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SizingMode {
+    /// Keeps the weights chosen by the strategy
+    Fixed,
+    /// Weights by the inverse of the volatility
+    InverseVolatility,
+    /// Equalizes the risk contributions, accounting for correlations
+    RiskParity,
+    /// Weights by the Kelly fraction of each pick, capped
+    Kelly,
+    /// Weights by the positive profit rate over the lookback period
+    ScoreProportional,
+}
+
+impl FromStr for SizingMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "fixed" => Ok(SizingMode::Fixed),
+            "inverse_volatility" => Ok(SizingMode::InverseVolatility),
+            "risk_parity" => Ok(SizingMode::RiskParity),
+            "kelly" => Ok(SizingMode::Kelly),
+            "score_proportional" => Ok(SizingMode::ScoreProportional),
+            _ => Err(anyhow::anyhow!("Unknown sizing mode: {}", s)),
+        }
+    }
+}
+
+/// Sizes the trades picked by a strategy.
+///
+/// The weights of the returned trades sum up to 1 over the picks, so the
+/// whole budget is used whatever the number of picks, except in Kelly sizing
+/// where the capped fractions below 1 in total leave the rest in SOL. Picks
+/// sized to 0 are dropped.
+#[derive(Debug, Clone, Copy)]
+pub struct Sizing {
+    pub mode: SizingMode,
+    /// Cap of the Kelly fraction of a single pick
+    pub kelly_max_fraction: f64,
+}
+
+impl Sizing {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            mode: config.sizing,
+            kelly_max_fraction: config.kelly_max_fraction,
+        }
+    }
+
+    pub fn size(&self, trades: Vec<Trade>, candidates: &[CandidateData]) -> Vec<Trade> {
+        if trades.is_empty() {
+            return trades;
+        }
+
+        let candidates = trades
+            .iter()
+            .filter_map(|t| candidates.iter().find(|c| c.token == t.token))
+            .collect::<Vec<_>>();
+        if candidates.len() != trades.len() {
+            tracing::warn!("Missing market data of the picks, keep the strategy weights");
+            return normalized(trades.iter().map(|t| t.weight).collect(), true)
+                .map(|weights| with_weights(trades, weights))
+                .unwrap_or_default();
+        }
+
+        let prices = candidates.iter().map(|c| c.prices()).collect::<Vec<_>>();
+        let weights = match self.mode {
+            SizingMode::Fixed => normalized(trades.iter().map(|t| t.weight).collect(), true),
+            SizingMode::InverseVolatility => normalized(
+                prices
+                    .iter()
+                    .map(|p| inverse(realized_volatility(p)))
+                    .collect(),
+                true,
+            ),
+            SizingMode::RiskParity => normalized(risk_parity(&prices), true),
+            SizingMode::Kelly => at_most_one(
+                prices
+                    .iter()
+                    .map(|p| kelly_fraction(p).min(self.kelly_max_fraction))
+                    .collect(),
+            ),
+            SizingMode::ScoreProportional => normalized(
+                candidates
+                    .iter()
+                    .map(|c| c.performance().profit_rate())
+                    .collect(),
+                true,
+            ),
+        };
+
+        match weights {
+            Some(weights) => with_weights(trades, weights),
+            None => {
+                tracing::warn!("No pick has a positive Kelly fraction, skip buying");
+                vec![]
+            }
+        }
+    }
+}
+
+fn inverse(value: f64) -> f64 {
+    if value > 0.0 {
+        1.0 / value
+    } else {
+        0.0
+    }
+}
+
+fn with_weights(trades: Vec<Trade>, weights: Vec<f64>) -> Vec<Trade> {
+    trades
+        .into_iter()
+        .zip(weights)
+        .filter(|(_, weight)| *weight > 0.0)
+        .map(|(trade, weight)| Trade { weight, ..trade })
+        .collect()
+}
+
+/// Scales the positive parts of the raw weights to sum up to 1.
+///
+/// Falls back to equal weights if no raw weight is positive and
+/// `equal_fallback` is set, otherwise returns `None`.
+fn normalized(raw: Vec<f64>, equal_fallback: bool) -> Option<Vec<f64>> {
+    let raw = raw
+        .into_iter()
+        .map(|w| if w.is_finite() { w.max(0.0) } else { 0.0 })
+        .collect::<Vec<_>>();
+    let total: f64 = raw.iter().sum();
+
+    if total > 0.0 {
+        Some(raw.iter().map(|w| w / total).collect())
+    } else if equal_fallback && !raw.is_empty() {
+        Some(vec![1.0 / raw.len() as f64; raw.len()])
+    } else {
+        None
+    }
+}
+
+/// Keeps the positive parts of the raw weights, scaled down to sum up to 1
+/// if they exceed it. Returns `None` if no raw weight is positive.
+fn at_most_one(raw: Vec<f64>) -> Option<Vec<f64>> {
+    let raw = raw
+        .into_iter()
+        .map(|w| if w.is_finite() { w.max(0.0) } else { 0.0 })
+        .collect::<Vec<_>>();
+    let total: f64 = raw.iter().sum();
+
+    if total > 1.0 {
+        Some(raw.iter().map(|w| w / total).collect())
+    } else if total > 0.0 {
+        Some(raw)
+    } else {
+        None
+    }
+}
+
+fn log_returns(prices: &[f64]) -> Vec<f64> {
+    prices
+        .windows(2)
+        .map(|w| {
+            if w[0] > 0.0 && w[1] > 0.0 {
+                (w[1] / w[0]).ln()
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Continuous Kelly fraction, mean over variance of the log returns.
+fn kelly_fraction(prices: &[f64]) -> f64 {
+    let returns = log_returns(prices);
+    if returns.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    if variance > 0.0 {
+        mean / variance
+    } else {
+        0.0
+    }
+}
+
+/// Equal risk contribution weights of the price series, computed from the
+/// covariance of their latest common returns.
+fn risk_parity(prices: &[Vec<f64>]) -> Vec<f64> {
+    const ITERATIONS: usize = 500;
+
+    let len = prices.iter().map(|p| p.len()).min().unwrap_or_default();
+    let returns = prices
+        .iter()
+        .map(|p| log_returns(&p[p.len() - len..]))
+        .collect::<Vec<_>>();
+    let n = returns.len();
+    let periods = len.saturating_sub(1);
+    if periods < 2 {
+        return vec![0.0; n];
+    }
+
+    let means = returns
+        .iter()
+        .map(|r| r.iter().sum::<f64>() / periods as f64)
+        .collect::<Vec<_>>();
+    let covariance = |i: usize, j: usize| {
+        (0..periods)
+            .map(|t| (returns[i][t] - means[i]) * (returns[j][t] - means[j]))
+            .sum::<f64>()
+            / (periods - 1) as f64
+    };
+    let cov = (0..n)
+        .map(|i| (0..n).map(|j| covariance(i, j)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    // Start from inverse volatility and move each weight toward the average
+    // risk contribution
+    let mut weights = (0..n)
+        .map(|i| inverse(cov[i][i].sqrt()))
+        .collect::<Vec<_>>();
+    for _ in 0..ITERATIONS {
+        let contributions = (0..n)
+            .map(|i| weights[i] * (0..n).map(|j| cov[i][j] * weights[j]).sum::<f64>())
+            .collect::<Vec<_>>();
+        let target = contributions.iter().sum::<f64>() / n as f64;
+        for i in 0..n {
+            if contributions[i] > 0.0 {
+                weights[i] *= (target / contributions[i]).sqrt();
+            }
+        }
+
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            break;
+        }
+        weights.iter_mut().for_each(|w| *w /= total);
+    }
+
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::tests::candidate;
+
+    fn sizing(mode: SizingMode) -> Sizing {
+        Sizing {
+            mode,
+            kelly_max_fraction: 2.0,
+        }
+    }
+
+    fn trades(candidates: &[CandidateData], weights: &[f64]) -> Vec<Trade> {
+        candidates
+            .iter()
+            .zip(weights)
+            .map(|(c, &weight)| Trade {
+                token: c.token.clone(),
+                weight,
+            })
+            .collect()
+    }
+
+    fn weight_sum(trades: &[Trade]) -> f64 {
+        trades.iter().map(|t| t.weight).sum()
+    }
+
+    #[test]
+    fn test_fixed_is_normalized() {
+        let candidates = vec![candidate("A", &[1.0, 1.1]), candidate("B", &[1.0, 1.2])];
+
+        let sized = sizing(SizingMode::Fixed).size(trades(&candidates, &[0.5, 0.3]), &candidates);
+        assert!((sized[0].weight - 0.625).abs() < 1e-9);
+        assert!((weight_sum(&sized) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_risk_parity_of_two_matches_inverse_volatility() {
+        let candidates = vec![
+            candidate("CALM", &[1.0, 1.01, 1.0, 1.02, 1.01, 1.03]),
+            candidate("WILD", &[1.0, 1.2, 0.9, 1.1, 1.0, 1.3]),
+        ];
+        let trades = || trades(&candidates, &[0.5, 0.5]);
+
+        let inverse = sizing(SizingMode::InverseVolatility).size(trades(), &candidates);
+        let parity = sizing(SizingMode::RiskParity).size(trades(), &candidates);
+        assert!(inverse[0].weight > inverse[1].weight);
+        for (a, b) in inverse.iter().zip(parity.iter()) {
+            assert!((a.weight - b.weight).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_kelly_drops_losers_and_caps() {
+        let candidates = vec![
+            candidate("UP", &[1.0, 1.05, 1.04, 1.1, 1.09, 1.15]),
+            candidate("STEADY", &[1.0, 1.01, 1.02, 1.03, 1.04, 1.05]),
+            candidate("DOWN", &[1.0, 0.95, 0.96, 0.9, 0.91, 0.85]),
+        ];
+
+        let sized = sizing(SizingMode::Kelly).size(trades(&candidates, &[0.4; 3]), &candidates);
+        // Both winners exceed the cap and DOWN has no edge
+        assert_eq!(sized.len(), 2);
+        assert!(sized.iter().all(|t| (t.weight - 0.5).abs() < 1e-9));
+
+        // Capped below 1 in total, the rest stays in SOL
+        let capped = Sizing {
+            mode: SizingMode::Kelly,
+            kelly_max_fraction: 0.3,
+        };
+        let sized = capped.size(trades(&candidates, &[0.4; 3]), &candidates);
+        assert_eq!(sized.len(), 2);
+        assert!(sized.iter().all(|t| (t.weight - 0.3).abs() < 1e-9));
+        assert!((weight_sum(&sized) - 0.6).abs() < 1e-9);
+
+        let losers = vec![candidates.into_iter().nth(2).unwrap()];
+        let sized = sizing(SizingMode::Kelly).size(trades(&losers, &[1.0]), &losers);
+        assert!(sized.is_empty());
+    }
+
+    #[test]
+    fn test_score_proportional() {
+        let candidates = vec![
+            candidate("A", &[1.0, 1.3]),
+            candidate("B", &[1.0, 1.1]),
+            candidate("C", &[1.0, 0.9]),
+        ];
+
+        let sized = sizing(SizingMode::ScoreProportional)
+            .size(trades(&candidates, &[0.5, 0.3, 0.2]), &candidates);
+        assert_eq!(sized.len(), 2);
+        assert!((sized[0].weight - 0.75).abs() < 1e-9);
+    }
+}
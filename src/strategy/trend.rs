@@ -0,0 +1,72 @@
+use anyhow::Result;
+
+use std::cmp::Ordering;
+
+use super::{CandidateData, Strategy, Trade};
+use crate::indicators::{macd, rsi};
+
+/// Follows the candidates in an accelerating uptrend that are not overbought
+/// yet.
+///
+/// A candidate qualifies when both its MACD line and histogram are positive
+/// and its RSI is below [`TrendStrategy::OVERBOUGHT_RSI`]. Qualified
+/// candidates are ranked by their MACD relative to the price and weighted
+/// evenly.
+pub struct TrendStrategy {
+    max_picks: usize,
+}
+
+impl TrendStrategy {
+    const OVERBOUGHT_RSI: f64 = 70.0;
+
+    pub fn new(max_picks: usize) -> Self {
+        Self { max_picks }
+    }
+
+    /// MACD relative to the latest price if the candidate qualifies.
+    fn score(prices: &[f64]) -> Option<f64> {
+        let last_price = *prices.last()?;
+        let last_macd = *macd(prices, 12, 26, 9).last()?;
+        let last_rsi = *rsi(prices, 14).last()?;
+
+        if last_macd.macd > 0.0 && last_macd.histogram > 0.0 && last_rsi < Self::OVERBOUGHT_RSI {
+            Some(last_macd.macd / last_price)
+        } else {
+            None
+        }
+    }
+}
+
+impl Strategy for TrendStrategy {
+    fn name(&self) -> &'static str {
+        "trend"
+    }
+
+    fn select(&self, candidates: &[CandidateData]) -> Result<Vec<Trade>> {
+        let mut scored = candidates
+            .iter()
+            .filter_map(|c| Self::score(&c.prices()).map(|score| (c, score)))
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+
+        for (candidate, score) in &scored {
+            tracing::info!(
+                "{:<10}: relative MACD: {:>8.4}",
+                candidate.token.symbol,
+                score
+            );
+        }
+
+        let picks = scored.len().min(self.max_picks);
+        let trades = scored
+            .into_iter()
+            .take(picks)
+            .map(|(candidate, _)| Trade {
+                token: candidate.token.clone(),
+                weight: 1.0 / picks as f64,
+            })
+            .collect();
+
+        Ok(trades)
+    }
+}
@@ -0,0 +1,147 @@
+use crate::config::Config;
+
+use super::PreparedSwap;
+
+/// Rejects quotes that move the price too much or stray from an independent
+/// price, as quoted by manipulated or thin pools.
+///
+/// A threshold of 0 disables the check. Quotes without a price impact are
+/// rejected while the price impact is limited.
+#[derive(Debug, Clone)]
+pub struct QuoteGuard {
+    /// Maximum price impact of the quote, as a fraction
+    pub max_price_impact: f64,
+    /// Maximum deviation of the quoted price from the reference price, as a
+    /// fraction of the reference price
+    pub max_price_deviation: f64,
+}
+
+impl QuoteGuard {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_price_impact: config.quote_max_price_impact,
+            max_price_deviation: config.quote_max_price_deviation,
+        }
+    }
+
+    /// Checks a prepared swap of a token with `decimals` against the price
+    /// impact limit, and against `reference_price` in SOL per UI unit when
+    /// known, returning the reason of the rejection if any.
+    pub fn check(
+        &self,
+        prepared: &PreparedSwap,
+        decimals: u8,
+        reference_price: Option<f64>,
+    ) -> Result<(), String> {
+        let quoted_price = prepared.price_in_sol(decimals);
+
+        if self.max_price_impact > 0.0 {
+            match prepared.price_impact {
+                Some(price_impact) if price_impact > self.max_price_impact => {
+                    return Err(format!(
+                        "price impact {:.2}% above {:.2}%, quoted price {} SOL, reference price {} SOL",
+                        price_impact * 100.0,
+                        self.max_price_impact * 100.0,
+                        quoted_price,
+                        optional_price(reference_price),
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    return Err(format!(
+                        "unknown price impact, limited to {:.2}%, quoted price {} SOL",
+                        self.max_price_impact * 100.0,
+                        quoted_price,
+                    ));
+                }
+            }
+        }
+
+        if let Some(reference_price) = reference_price.filter(|price| *price > 0.0) {
+            let deviation = (quoted_price - reference_price).abs() / reference_price;
+            if self.max_price_deviation > 0.0 && deviation > self.max_price_deviation {
+                return Err(format!(
+                    "quoted price {} SOL deviates {:.2}% from reference price {} SOL, above {:.2}%",
+                    quoted_price,
+                    deviation * 100.0,
+                    reference_price,
+                    self.max_price_deviation * 100.0
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn optional_price(price: Option<f64>) -> String {
+    price
+        .map(|price| price.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::*;
+    use crate::swap::params::SwapSettings;
+    use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+
+    /// A buy of 1 SOL for `out` UI tokens of 6 decimals.
+    fn buy(out: f64, price_impact: Option<f64>) -> PreparedSwap {
+        PreparedSwap {
+            input_mint: spl_token::native_mint::id(),
+            output_mint: Pubkey::new_unique(),
+            in_amount: LAMPORTS_PER_SOL,
+            out_amount: (out * 1_000_000.0) as u64,
+            price_impact,
+            settings: SwapSettings {
+                slippage_bps: 100,
+                compute_unit_price: 0,
+            },
+            transaction: VersionedTransaction::default(),
+        }
+    }
+
+    #[test]
+    fn test_quote_guard() {
+        let guard = QuoteGuard {
+            max_price_impact: 0.03,
+            max_price_deviation: 0.05,
+        };
+
+        assert!(guard
+            .check(&buy(100.0, Some(0.01)), 6, Some(0.0101))
+            .is_ok());
+        assert!(guard
+            .check(&buy(100.0, Some(0.05)), 6, Some(0.01))
+            .unwrap_err()
+            .contains("price impact"));
+        // Quoted at 0.0125 SOL per token against 0.01 SOL on the market
+        let reason = guard
+            .check(&buy(80.0, Some(0.01)), 6, Some(0.01))
+            .unwrap_err();
+        assert!(reason.contains("0.0125 SOL") && reason.contains("0.01 SOL"));
+        // No reference price, no deviation check
+        assert!(guard.check(&buy(80.0, Some(0.01)), 6, None).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_price_impact() {
+        let guard = QuoteGuard {
+            max_price_impact: 0.03,
+            max_price_deviation: 0.0,
+        };
+        assert!(guard
+            .check(&buy(100.0, None), 6, None)
+            .unwrap_err()
+            .contains("unknown price impact"));
+
+        // Not limited, not required
+        let guard = QuoteGuard {
+            max_price_impact: 0.0,
+            ..guard
+        };
+        assert!(guard.check(&buy(100.0, None), 6, None).is_ok());
+    }
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use super::{PreparedSwap, SwapExecutor};
+use crate::jupiter::swap::{prepare_swap, send_swap, simulate_swap};
+
+/// Swaps routed by the Jupiter v6 API.
+pub struct JupiterExecutor;
+
+#[async_trait]
+impl SwapExecutor for JupiterExecutor {
+    fn name(&self) -> &'static str {
+        "jupiter"
+    }
+
+    async fn prepare(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        amount: u64,
+        slippage_bps: u64,
+    ) -> Result<PreparedSwap> {
+        prepare_swap(input_mint, output_mint, amount, slippage_bps).await
+    }
+
+    async fn simulate(&self, prepared: &PreparedSwap) -> Result<()> {
+        simulate_swap(prepared).await
+    }
+
+    async fn send(&self, prepared: &PreparedSwap) -> Result<Signature> {
+        send_swap(prepared).await
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use solana_sdk::{
+    message::{Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
+};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::params::SwapSettings;
+use super::sender::SendError;
+use super::{PreparedSwap, SwapExecutor};
+use crate::constant::*;
+use crate::fill::SwapFill;
+use crate::journal::IntentKind;
+
+/// Constant-product pool of a token against SOL, in raw amounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockPool {
+    pub sol_reserve: u64,
+    pub token_reserve: u64,
+}
+
+#[derive(Default)]
+struct MockState {
+    pools: HashMap<Pubkey, MockPool>,
+    fills: HashMap<Signature, SwapFill>,
+}
+
+/// In-process AMM of constant-product pools against SOL. Swaps execute at
+/// the reserves when sent, and fail beyond the slippage of the quote.
+pub struct MockAmm {
+    fee_bps: u64,
+    state: Mutex<MockState>,
+}
+
+impl MockAmm {
+    pub fn new(fee_bps: u64) -> Self {
+        Self {
+            fee_bps,
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    pub fn add_pool(&self, mint: Pubkey, sol_reserve: u64, token_reserve: u64) {
+        self.state.lock().unwrap().pools.insert(
+            mint,
+            MockPool {
+                sol_reserve,
+                token_reserve,
+            },
+        );
+    }
+
+    pub fn pool(&self, mint: &Pubkey) -> Option<MockPool> {
+        self.state.lock().unwrap().pools.get(mint).copied()
+    }
+
+    /// Token traded against SOL and whether SOL goes in.
+    fn pair(input_mint: &Pubkey, output_mint: &Pubkey) -> Result<(Pubkey, bool)> {
+        let sol_mint = spl_token::native_mint::id();
+        match (*input_mint == sol_mint, *output_mint == sol_mint) {
+            (true, false) => Ok((*output_mint, true)),
+            (false, true) => Ok((*input_mint, false)),
+            _ => Err(anyhow::anyhow!(
+                "Mock AMM only swaps against SOL: {} to {}",
+                input_mint,
+                output_mint
+            )),
+        }
+    }
+
+    fn amount_out(&self, pool: &MockPool, sol_in: bool, amount: u64) -> u64 {
+        let (reserve_in, reserve_out) = if sol_in {
+            (pool.sol_reserve, pool.token_reserve)
+        } else {
+            (pool.token_reserve, pool.sol_reserve)
+        };
+        let amount_in = amount as u128 * (10_000 - self.fee_bps) as u128 / 10_000;
+        (reserve_out as u128 * amount_in / (reserve_in as u128 + amount_in)) as u64
+    }
+
+    /// Shortfall of the output against the spot price, fees excluded.
+    fn price_impact(&self, pool: &MockPool, sol_in: bool, amount: u64) -> f64 {
+        let (reserve_in, reserve_out) = if sol_in {
+            (pool.sol_reserve, pool.token_reserve)
+        } else {
+            (pool.token_reserve, pool.sol_reserve)
+        };
+        let amount_in = amount as f64 * (10_000 - self.fee_bps) as f64 / 10_000.0;
+        let spot_out = amount_in * reserve_out as f64 / reserve_in as f64;
+        if spot_out == 0.0 {
+            return 0.0;
+        }
+
+        1.0 - self.amount_out(pool, sol_in, amount) as f64 / spot_out
+    }
+}
+
+#[async_trait]
+impl SwapExecutor for MockAmm {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn prepare(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        amount: u64,
+        slippage_bps: u64,
+    ) -> Result<PreparedSwap> {
+        let (mint, sol_in) = Self::pair(input_mint, output_mint)?;
+        let pool = self
+            .pool(&mint)
+            .ok_or(anyhow::anyhow!("No mock pool for {}", mint))?;
+
+        Ok(PreparedSwap {
+            input_mint: *input_mint,
+            output_mint: *output_mint,
+            in_amount: amount,
+            out_amount: self.amount_out(&pool, sol_in, amount),
+            price_impact: Some(self.price_impact(&pool, sol_in, amount)),
+            settings: SwapSettings {
+                slippage_bps,
+                compute_unit_price: 0,
+            },
+            transaction: VersionedTransaction {
+                signatures: vec![Signature::new_unique()],
+                message: VersionedMessage::Legacy(Message::default()),
+            },
+        })
+    }
+
+    async fn simulate(&self, _prepared: &PreparedSwap) -> Result<()> {
+        Ok(())
+    }
+
+    async fn send(&self, prepared: &PreparedSwap) -> Result<Signature> {
+        let (mint, sol_in) = Self::pair(&prepared.input_mint, &prepared.output_mint)?;
+        let mut state = self.state.lock().unwrap();
+        let pool = state
+            .pools
+            .get(&mint)
+            .copied()
+            .ok_or(anyhow::anyhow!("No mock pool for {}", mint))?;
+
+        let out_amount = self.amount_out(&pool, sol_in, prepared.in_amount);
+        let slippage_bps = prepared.settings.slippage_bps.min(10_000);
+        let min_out = prepared.out_amount as u128 * (10_000 - slippage_bps) as u128 / 10_000;
+        if (out_amount as u128) < min_out {
+            tracing::warn!("Mock swap out {} below the minimum {}", out_amount, min_out);
+            return Err(SendError::SlippageExceeded.into());
+        }
+
+        let (pool, fill) = if sol_in {
+            let pool = MockPool {
+                sol_reserve: pool.sol_reserve + prepared.in_amount,
+                token_reserve: pool.token_reserve - out_amount,
+            };
+            let fill = SwapFill {
+                fee_lamports: 0,
+                net_sol: prepared.in_amount,
+                token_amount: out_amount,
+                quoted_out: prepared.out_amount,
+                actual_out: out_amount,
+            };
+            (pool, fill)
+        } else {
+            let pool = MockPool {
+                sol_reserve: pool.sol_reserve - out_amount,
+                token_reserve: pool.token_reserve + prepared.in_amount,
+            };
+            let fill = SwapFill {
+                fee_lamports: 0,
+                net_sol: out_amount,
+                token_amount: prepared.in_amount,
+                quoted_out: prepared.out_amount,
+                actual_out: out_amount,
+            };
+            (pool, fill)
+        };
+
+        let sig = prepared.signature();
+        state.pools.insert(mint, pool);
+        state.fills.insert(sig, fill);
+        Ok(sig)
+    }
+
+    fn signature_status(&self, sig: &Signature) -> Result<Option<Result<(), TransactionError>>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.fills.contains_key(sig).then_some(Ok(())))
+    }
+
+    async fn fill(
+        &self,
+        sig: &Signature,
+        _mint: &Pubkey,
+        _kind: IntentKind,
+        _quoted_out: u64,
+    ) -> Result<SwapFill> {
+        let state = self.state.lock().unwrap();
+        state
+            .fills
+            .get(sig)
+            .copied()
+            .ok_or(anyhow::anyhow!("Unknown mock swap {}", sig))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_swaps() {
+        let (sol, token) = (spl_token::native_mint::id(), Pubkey::new_unique());
+        let amm = MockAmm::new(30);
+        amm.add_pool(token, 100 * LAMPORTS_PER_SOL, 1_000_000_000);
+
+        let buy = amm
+            .prepare(&sol, &token, LAMPORTS_PER_SOL, 100)
+            .await
+            .unwrap();
+        let sig = amm.send(&buy).await.unwrap();
+        let fill = amm.fill(&sig, &token, IntentKind::Buy, 0).await.unwrap();
+        assert_eq!(fill.token_amount, buy.out_amount);
+        assert!(fill.token_amount < 10_000_000 && fill.token_amount > 9_800_000);
+        assert_eq!(amm.signature_status(&sig).unwrap(), Some(Ok(())));
+
+        // Selling it all back loses the fees and the price impact
+        let sell = amm
+            .prepare(&token, &sol, fill.token_amount, 100)
+            .await
+            .unwrap();
+        amm.send(&sell).await.unwrap();
+        assert!(sell.out_amount < LAMPORTS_PER_SOL);
+        assert!((buy.price_impact.unwrap() - 0.00987).abs() < 1e-4);
+        assert_eq!(amm.pool(&token).unwrap().token_reserve, 1_000_000_000);
+
+        // A large swap landing first moves the price beyond the slippage
+        let late = amm
+            .prepare(&sol, &token, LAMPORTS_PER_SOL, 100)
+            .await
+            .unwrap();
+        let early = amm
+            .prepare(&sol, &token, 10 * LAMPORTS_PER_SOL, 100)
+            .await
+            .unwrap();
+        amm.send(&early).await.unwrap();
+        assert!(amm.send(&late).await.is_err());
+        assert_eq!(amm.signature_status(&late.signature()).unwrap(), None);
+
+        assert!(amm.prepare(&token, &token, 1, 100).await.is_err());
+    }
+}
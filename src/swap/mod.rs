@@ -0,0 +1,150 @@
+//! Swap execution, behind the [`SwapExecutor`] trait the portfolio trades
+//! through: Jupiter routing or a direct Raydium CLMM swap, and an in-process
+//! mock AMM that needs no network for the tests.
+pub mod guard;
+pub mod jupiter;
+#[cfg(test)]
+pub mod mock;
+pub mod params;
+pub mod raydium;
+pub mod sender;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    transaction::TransactionError, transaction::VersionedTransaction,
+};
+
+use std::str::FromStr;
+
+use crate::client::get_confirmed_client;
+use crate::config::Config;
+use crate::constant::*;
+use crate::fill::{fetch_fill, SwapFill};
+use crate::journal::IntentKind;
+use params::SwapSettings;
+use sender::TransactionSender;
+
+/// A quoted and signed swap, not sent yet.
+#[derive(Debug, Clone)]
+pub struct PreparedSwap {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub in_amount: u64,
+    /// Quoted output, in raw amount of the output mint
+    pub out_amount: u64,
+    /// Estimated price impact of the swap as a fraction, if quoted
+    pub price_impact: Option<f64>,
+    pub settings: SwapSettings,
+    pub transaction: VersionedTransaction,
+}
+
+impl PreparedSwap {
+    /// Signature the transaction lands with, known before sending it.
+    pub fn signature(&self) -> Signature {
+        self.transaction.signatures[0]
+    }
+
+    /// Quoted price of one UI unit of the token traded against SOL, in SOL.
+    pub fn price_in_sol(&self, decimals: u8) -> f64 {
+        let (sol_amount, token_amount) = if self.input_mint == spl_token::native_mint::id() {
+            (self.in_amount, self.out_amount)
+        } else {
+            (self.out_amount, self.in_amount)
+        };
+        if token_amount == 0 {
+            return 0.0;
+        }
+
+        let ui_amount = token_amount as f64 / 10_f64.powi(decimals as i32);
+        sol_amount as f64 / LAMPORTS_PER_SOL as f64 / ui_amount
+    }
+}
+
+/// Quote, sign and send swaps in two steps, so the signature can be
+/// journaled before the transaction leaves.
+#[async_trait]
+pub trait SwapExecutor: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Quotes a swap of `amount` raw `input_mint` to `output_mint` within
+    /// `slippage_bps` and signs it with an estimated priority fee.
+    async fn prepare(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        amount: u64,
+        slippage_bps: u64,
+    ) -> Result<PreparedSwap>;
+
+    /// Simulates a prepared swap before it is journaled and sent, failing
+    /// with a [`sender::SendError`] when the reason is known. Nothing is
+    /// broadcast, a failed simulation leaves nothing to wait for on chain.
+    async fn simulate(&self, prepared: &PreparedSwap) -> Result<()> {
+        let sender = TransactionSender::new(Config::get().solana_rpc_url.clone());
+        let units_consumed = sender.simulate(&prepared.transaction).await?;
+        tracing::info!(
+            "Simulated {}: {} compute units",
+            prepared.signature(),
+            units_consumed
+        );
+
+        Ok(())
+    }
+
+    /// Sends a simulated swap and waits for its confirmation, failing with a
+    /// [`sender::SendError`] when the reason is known.
+    async fn send(&self, prepared: &PreparedSwap) -> Result<Signature>;
+
+    /// Status of a swap transaction, None if it is unknown.
+    fn signature_status(&self, sig: &Signature) -> Result<Option<Result<(), TransactionError>>> {
+        Ok(
+            get_confirmed_client().get_signature_status_with_commitment_and_history(
+                sig,
+                CommitmentConfig::confirmed(),
+                true,
+            )?,
+        )
+    }
+
+    /// Fill of a confirmed swap of `mint`, from its transaction meta.
+    async fn fill(
+        &self,
+        sig: &Signature,
+        mint: &Pubkey,
+        kind: IntentKind,
+        quoted_out: u64,
+    ) -> Result<SwapFill> {
+        fetch_fill(sig, mint, kind, quoted_out)
+    }
+}
+
+/// The [`SwapExecutor`] the portfolio trades through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapBackend {
+    Jupiter,
+    Raydium,
+}
+
+impl FromStr for SwapBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "jupiter" => Ok(SwapBackend::Jupiter),
+            "raydium" => Ok(SwapBackend::Raydium),
+            _ => Err(anyhow::anyhow!("Unknown swap backend: {}", s)),
+        }
+    }
+}
+
+impl SwapBackend {
+    pub fn executor(&self) -> Box<dyn SwapExecutor> {
+        match self {
+            SwapBackend::Jupiter => Box::new(jupiter::JupiterExecutor),
+            SwapBackend::Raydium => Box::new(raydium::RaydiumClmmExecutor),
+        }
+    }
+}
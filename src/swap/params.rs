@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::client::get_confirmed_client;
+use crate::config::Config;
+use crate::constant::*;
+
+/// Slippage tolerance and priority fee a swap was sent with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapSettings {
+    pub slippage_bps: u64,
+    /// Compute unit price, in micro-lamports
+    pub compute_unit_price: u64,
+}
+
+/// Priority fee estimated from the fees recently paid to write the accounts
+/// of a transaction.
+#[derive(Debug, Clone)]
+pub struct PriorityFees {
+    /// Percentile of the recent fees paid, from 0 to 100
+    pub percentile: f64,
+    /// Highest compute unit price paid, in micro-lamports
+    pub max_micro_lamports: u64,
+}
+
+impl PriorityFees {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            percentile: config.priority_fee_percentile,
+            max_micro_lamports: config.priority_fee_max_micro_lamports,
+        }
+    }
+
+    /// Compute unit price for a transaction writing `accounts`, the default
+    /// price if the recent fees cannot be read.
+    pub fn estimate(&self, accounts: &[Pubkey]) -> u64 {
+        match get_confirmed_client().get_recent_prioritization_fees(accounts) {
+            Ok(fees) => {
+                let fees = fees
+                    .iter()
+                    .map(|fee| fee.prioritization_fee)
+                    .collect::<Vec<_>>();
+                self.pick(&fees)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read recent prioritization fees: {}", e);
+                COMPUTE_UNIT_PRICE.min(self.max_micro_lamports)
+            }
+        }
+    }
+
+    /// Nearest-rank percentile of the fees, capped.
+    pub fn pick(&self, fees: &[u64]) -> u64 {
+        if fees.is_empty() {
+            return COMPUTE_UNIT_PRICE.min(self.max_micro_lamports);
+        }
+
+        let mut fees = fees.to_vec();
+        fees.sort_unstable();
+        let rank = (self.percentile.clamp(0.0, 100.0) / 100.0 * fees.len() as f64).ceil() as usize;
+        fees[rank.clamp(1, fees.len()) - 1].min(self.max_micro_lamports)
+    }
+}
+
+/// Slippage tolerance of a token, widened by its volatility and by the
+/// share of its daily volume a trade takes.
+#[derive(Debug, Clone)]
+pub struct SlippagePolicy {
+    pub min_bps: u64,
+    pub max_bps: u64,
+}
+
+impl SlippagePolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            min_bps: config.slippage_min_bps,
+            max_bps: config.slippage_max_bps,
+        }
+    }
+
+    /// `volatility` is the standard deviation of the recent returns and
+    /// `participation` the trade size over the 24h volume. Tokens without
+    /// market data get the widest tolerance.
+    pub fn slippage_bps(&self, volatility: Option<f64>, participation: Option<f64>) -> u64 {
+        let (Some(volatility), Some(participation)) = (volatility, participation) else {
+            return self.max_bps;
+        };
+
+        let bps = self.min_bps as f64 + (volatility + participation) * 10_000.0;
+        (bps.round() as u64).clamp(self.min_bps, self.max_bps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_fee_and_slippage() {
+        let fees = PriorityFees {
+            percentile: 75.0,
+            max_micro_lamports: 50_000,
+        };
+        assert_eq!(fees.pick(&[0, 10, 1_000, 20, 5_000, 0, 0, 30]), 30);
+        assert_eq!(fees.pick(&[0, 100_000, 200_000, 300_000]), 50_000);
+        assert_eq!(fees.pick(&[]), COMPUTE_UNIT_PRICE.min(50_000));
+
+        let slippage = SlippagePolicy {
+            min_bps: 50,
+            max_bps: 500,
+        };
+        assert_eq!(slippage.slippage_bps(Some(0.005), Some(0.001)), 110);
+        assert_eq!(slippage.slippage_bps(Some(0.08), Some(0.0)), 500);
+        assert_eq!(slippage.slippage_bps(Some(0.0), None), 500);
+    }
+}
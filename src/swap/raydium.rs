@@ -0,0 +1,231 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use clmm_cli::ClmmCommands;
+use common::rpc;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::VersionedTransaction;
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::params::{PriorityFees, SwapSettings};
+use super::sender::TransactionSender;
+use super::{PreparedSwap, SwapExecutor};
+use crate::client::{get_confirmed_client, get_http_client};
+use crate::config::Config;
+use crate::token::jimmy::JimmyToken;
+use crate::token::raydium::{generate_pool_id, get_amm_config_pubkey, get_common_config};
+use crate::wallet::Wallet;
+
+#[cfg(not(feature = "devnet"))]
+const POOL_INFO_URL: &str = "https://api-v3.raydium.io/pools/info/ids";
+#[cfg(feature = "devnet")]
+const POOL_INFO_URL: &str = "https://api-v3-devnet.raydium.io/pools/info/ids";
+
+/// Swaps straight through a Raydium CLMM pool: the JIMMY pool, or the pool
+/// of the pair on the first AMM config for other tokens.
+pub struct RaydiumClmmExecutor;
+
+impl RaydiumClmmExecutor {
+    async fn pool_id(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Result<Pubkey> {
+        let jimmy_token = JimmyToken::get().await;
+        let jimmy_mint = jimmy_token.mint_pubkey();
+        if *input_mint == jimmy_mint || *output_mint == jimmy_mint {
+            return jimmy_token
+                .pool_id()
+                .ok_or(anyhow::anyhow!("Pool ID not found"));
+        }
+
+        let amm_config = get_amm_config_pubkey().await?;
+        Ok(generate_pool_id(
+            &get_common_config(),
+            &amm_config,
+            input_mint,
+            output_mint,
+        ))
+    }
+
+    /// Output of the swap at the pool price net of the trade fee, price
+    /// impact excluded.
+    async fn quote(&self, pool_id: &Pubkey, input_mint: &Pubkey, amount: u64) -> Result<u64> {
+        let pool = PoolInfo::fetch(pool_id).await?;
+        let (in_decimals, out_decimals) = if *input_mint == pool.mint_a {
+            (pool.decimals_a, pool.decimals_b)
+        } else {
+            (pool.decimals_b, pool.decimals_a)
+        };
+        let out_ui_amount = amount as f64 / 10_f64.powi(in_decimals) * pool.price_of(input_mint);
+
+        Ok((out_ui_amount * (1.0 - pool.fee_rate) * 10_f64.powi(out_decimals)) as u64)
+    }
+}
+
+/// Current state of a CLMM pool, from the Raydium API.
+struct PoolInfo {
+    mint_a: Pubkey,
+    decimals_a: i32,
+    decimals_b: i32,
+    /// Price of one mint A in mint B
+    price: f64,
+    fee_rate: f64,
+}
+
+impl PoolInfo {
+    async fn fetch(pool_id: &Pubkey) -> Result<Self> {
+        let response: serde_json::Value = get_http_client()
+            .get(POOL_INFO_URL)
+            .query(&[("ids", pool_id.to_string())])
+            .send()
+            .await?
+            .json()
+            .await?;
+        let pool = &response["data"][0];
+
+        let mint = |side: &str| -> Result<(Pubkey, i32)> {
+            let address = pool[side]["address"]
+                .as_str()
+                .ok_or(anyhow::anyhow!("Invalid pool info of {}", pool_id))?;
+            let decimals = pool[side]["decimals"]
+                .as_i64()
+                .ok_or(anyhow::anyhow!("Invalid pool info of {}", pool_id))?;
+            Ok((Pubkey::from_str(address)?, decimals as i32))
+        };
+        let (mint_a, decimals_a) = mint("mintA")?;
+        let (_, decimals_b) = mint("mintB")?;
+        let price = pool["price"]
+            .as_f64()
+            .filter(|price| *price > 0.0)
+            .ok_or(anyhow::anyhow!("Invalid pool price of {}", pool_id))?;
+
+        Ok(Self {
+            mint_a,
+            decimals_a,
+            decimals_b,
+            price,
+            fee_rate: pool["feeRate"].as_f64().unwrap_or_default(),
+        })
+    }
+
+    /// Price of one UI unit of `mint` in the other mint of the pool.
+    fn price_of(&self, mint: &Pubkey) -> f64 {
+        if *mint == self.mint_a {
+            self.price
+        } else {
+            1.0 / self.price
+        }
+    }
+}
+
+/// Current price of one UI unit of `mint` in the other mint of a CLMM pool.
+pub async fn pool_price(pool_id: &Pubkey, mint: &Pubkey) -> Result<f64> {
+    Ok(PoolInfo::fetch(pool_id).await?.price_of(mint))
+}
+
+#[async_trait]
+impl SwapExecutor for RaydiumClmmExecutor {
+    fn name(&self) -> &'static str {
+        "raydium"
+    }
+
+    async fn prepare(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+        amount: u64,
+        slippage_bps: u64,
+    ) -> Result<PreparedSwap> {
+        let wallet = Wallet::get();
+        let pool_id = self.pool_id(input_mint, output_mint).await?;
+        let out_amount = self.quote(&pool_id, input_mint, amount).await?;
+        let compute_unit_price = PriorityFees::from_config(Config::get()).estimate(&[pool_id]);
+
+        let wsol_mint = spl_token::native_mint::id();
+        let wsol_ata = spl_associated_token_account::get_associated_token_address(
+            &wallet.pubkey(),
+            &wsol_mint,
+        );
+        let command = ClmmCommands::Swap {
+            pool_id,
+            user_input_token: spl_associated_token_account::get_associated_token_address(
+                &wallet.pubkey(),
+                input_mint,
+            ),
+            user_output_token: None,
+            amount_specified: amount,
+            limit_price: None,
+            base_in: true,
+            simulate: false,
+        };
+        tracing::info!("Preparing CLMM swap: {:?}", command);
+
+        let mut common_config = get_common_config();
+        common_config.set_slippage(slippage_bps as f64 / 10_000.0);
+        let mut signing_keypairs: Vec<Arc<dyn Signer>> =
+            vec![Arc::new(wallet.keypair().insecure_clone())];
+        let mut instructions =
+            clmm_cli::process_clmm_commands(command, &common_config, &mut signing_keypairs)?
+                .ok_or(anyhow::anyhow!("No instructions for the CLMM swap"))?;
+        // The pool trades wrapped SOL, wrapped within the swap so nothing is
+        // left wrapped when the swap is never sent
+        if *input_mint == wsol_mint {
+            let wrap = [
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &wallet.pubkey(),
+                    &wallet.pubkey(),
+                    &wsol_mint,
+                    &spl_token::id(),
+                ),
+                solana_sdk::system_instruction::transfer(&wallet.pubkey(), &wsol_ata, amount),
+                spl_token::instruction::sync_native(&spl_token::id(), &wsol_ata)?,
+            ];
+            instructions.splice(0..0, wrap);
+        }
+        instructions.insert(
+            0,
+            ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+        );
+        // Unwrap the SOL left in or received on the wrapped account
+        if *input_mint == wsol_mint || *output_mint == wsol_mint {
+            instructions.push(spl_token::instruction::close_account(
+                &spl_token::id(),
+                &wsol_ata,
+                &wallet.pubkey(),
+                &wallet.pubkey(),
+                &[],
+            )?);
+        }
+
+        let transaction = rpc::build_txn(
+            &get_confirmed_client(),
+            &instructions,
+            &wallet.pubkey(),
+            &signing_keypairs,
+        )?;
+
+        Ok(PreparedSwap {
+            input_mint: *input_mint,
+            output_mint: *output_mint,
+            in_amount: amount,
+            out_amount,
+            // Quoted at spot, rejected by the guard while the impact is limited
+            price_impact: None,
+            settings: SwapSettings {
+                slippage_bps,
+                compute_unit_price,
+            },
+            transaction: VersionedTransaction::from(transaction),
+        })
+    }
+
+    async fn send(&self, prepared: &PreparedSwap) -> Result<Signature> {
+        // Never rebuilt, as a new transaction would not match the journal
+        let sender = TransactionSender::new(Config::get().solana_rpc_url.clone());
+        let sig = sender.send(&prepared.transaction).await?;
+        tracing::info!("CLMM swap confirmed: {}", sig);
+
+        Ok(sig)
+    }
+}
@@ -0,0 +1,260 @@
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig, RpcTransactionConfig},
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::Signature,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use solana_transaction_status::{TransactionStatus, UiTransactionEncoding};
+
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// Program logs of a swap failing short of its minimum output.
+const SLIPPAGE_LOGS: &[&str] = &[
+    "slippagetoleranceexceeded",
+    "toolittleoutputreceived",
+    "exceeds desired slippage limit",
+];
+/// Program logs of a transfer of more than a balance holds.
+const INSUFFICIENT_FUNDS_LOGS: &[&str] = &["insufficient funds", "insufficient lamports"];
+
+/// Why a swap could not be sent or did not land.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendError {
+    /// The output fell short of the quote beyond the slippage tolerance
+    SlippageExceeded,
+    /// Not enough SOL or tokens for the swap and its fees
+    InsufficientFunds,
+    /// The blockhash expired before the transaction landed, it never will
+    BlockhashExpired,
+    /// A program failed the simulation
+    Simulation {
+        error: TransactionError,
+        logs: Vec<String>,
+        units_consumed: Option<u64>,
+    },
+    /// The transaction landed and failed
+    Failed(TransactionError),
+    /// The RPC node could not be reached or rejected a request
+    Rpc(String),
+}
+
+impl SendError {
+    /// Reason of a transaction failing with `error` and its program `logs`,
+    /// if it is a known one.
+    pub fn classify(error: &TransactionError, logs: &[String]) -> Option<Self> {
+        match error {
+            TransactionError::InsufficientFundsForFee
+            | TransactionError::InsufficientFundsForRent { .. } => {
+                return Some(SendError::InsufficientFunds)
+            }
+            TransactionError::BlockhashNotFound => return Some(SendError::BlockhashExpired),
+            _ => {}
+        }
+
+        let logs = logs.join("\n").to_lowercase();
+        if SLIPPAGE_LOGS.iter().any(|log| logs.contains(log)) {
+            Some(SendError::SlippageExceeded)
+        } else if INSUFFICIENT_FUNDS_LOGS.iter().any(|log| logs.contains(log)) {
+            Some(SendError::InsufficientFunds)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::SlippageExceeded => write!(f, "slippage tolerance exceeded"),
+            SendError::InsufficientFunds => write!(f, "insufficient funds"),
+            SendError::BlockhashExpired => write!(f, "blockhash expired"),
+            SendError::Simulation {
+                error,
+                logs,
+                units_consumed,
+            } => write!(
+                f,
+                "simulation failed: {}, {} compute units, last log: {}",
+                error,
+                units_consumed.unwrap_or_default(),
+                logs.last().map(String::as_str).unwrap_or("none")
+            ),
+            SendError::Failed(error) => write!(f, "transaction failed: {}", error),
+            SendError::Rpc(e) => write!(f, "rpc error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+impl From<solana_client::client_error::ClientError> for SendError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        SendError::Rpc(e.to_string())
+    }
+}
+
+/// Simulates signed transactions, then sends them as is while their
+/// blockhash is valid, so they keep their signature.
+pub struct TransactionSender {
+    client: RpcClient,
+    resend_interval: Duration,
+}
+
+impl TransactionSender {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            client: RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()),
+            resend_interval: Duration::from_millis(Config::get().swap_resend_interval_ms),
+        }
+    }
+
+    /// Simulates the transaction, returning the compute units it consumes.
+    pub async fn simulate(&self, transaction: &VersionedTransaction) -> Result<u64, SendError> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: false,
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = self
+            .client
+            .simulate_transaction_with_config(transaction, config)
+            .await?
+            .value;
+
+        let logs = result.logs.unwrap_or_default();
+        match result.err {
+            Some(error) => Err(SendError::classify(&error, &logs).unwrap_or(
+                SendError::Simulation {
+                    error,
+                    logs,
+                    units_consumed: result.units_consumed,
+                },
+            )),
+            None => Ok(result.units_consumed.unwrap_or_default()),
+        }
+    }
+
+    /// Sends the transaction, simulated already, until it is confirmed or
+    /// its blockhash expires.
+    pub async fn send(&self, transaction: &VersionedTransaction) -> Result<Signature, SendError> {
+        let sig = transaction.signatures[0];
+        let blockhash = *transaction.message.recent_blockhash();
+
+        // Resent by hand rather than by the RPC node
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            max_retries: Some(0),
+            ..RpcSendTransactionConfig::default()
+        };
+        loop {
+            if let Err(e) = self
+                .client
+                .send_transaction_with_config(transaction, config)
+                .await
+            {
+                tracing::warn!("Failed to send {}: {}", sig, e);
+            }
+            tokio::time::sleep(self.resend_interval).await;
+
+            // Checked before the status, so a transaction not found after
+            // the expiry can no longer land
+            let valid = self
+                .client
+                .is_blockhash_valid(&blockhash, CommitmentConfig::processed())
+                .await?;
+            match self.status(&sig).await? {
+                Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                    return self.landed(sig, status.status).await
+                }
+                // Processed, waiting for its confirmation
+                Some(_) => {}
+                None if valid => {}
+                None => return Err(SendError::BlockhashExpired),
+            }
+        }
+    }
+
+    /// Status of a recent transaction, None if it has not been processed.
+    async fn status(&self, sig: &Signature) -> Result<Option<TransactionStatus>, SendError> {
+        let statuses = self.client.get_signature_statuses(&[*sig]).await?.value;
+        Ok(statuses.into_iter().next().flatten())
+    }
+
+    async fn landed(
+        &self,
+        sig: Signature,
+        status: Result<(), TransactionError>,
+    ) -> Result<Signature, SendError> {
+        let Err(error) = status else {
+            tracing::info!(
+                "Transaction confirmed. View on Solscan: https://solscan.io/tx/{}",
+                sig
+            );
+            return Ok(sig);
+        };
+
+        let logs = self.logs(&sig).await;
+        Err(SendError::classify(&error, &logs).unwrap_or(SendError::Failed(error)))
+    }
+
+    /// Program logs of a landed transaction, best effort.
+    async fn logs(&self, sig: &Signature) -> Vec<String> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        match self.client.get_transaction_with_config(sig, config).await {
+            Ok(tx) => tx
+                .transaction
+                .meta
+                .and_then(|meta| Option::<Vec<String>>::from(meta.log_messages))
+                .unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("Failed to read the logs of {}: {}", sig, e);
+                vec![]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::InstructionError;
+
+    #[test]
+    fn test_classify_send_errors() {
+        let custom = TransactionError::InstructionError(3, InstructionError::Custom(6001));
+        let logs = vec![
+            "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 invoke [1]".to_string(),
+            "Program log: Error: SlippageToleranceExceeded".to_string(),
+        ];
+        assert_eq!(
+            SendError::classify(&custom, &logs),
+            Some(SendError::SlippageExceeded)
+        );
+
+        let logs = vec!["Program log: Error: insufficient funds".to_string()];
+        let transfer = TransactionError::InstructionError(2, InstructionError::Custom(1));
+        assert_eq!(
+            SendError::classify(&transfer, &logs),
+            Some(SendError::InsufficientFunds)
+        );
+        assert_eq!(
+            SendError::classify(&TransactionError::InsufficientFundsForFee, &[]),
+            Some(SendError::InsufficientFunds)
+        );
+        assert_eq!(
+            SendError::classify(&TransactionError::BlockhashNotFound, &[]),
+            Some(SendError::BlockhashExpired)
+        );
+        assert_eq!(SendError::classify(&custom, &[]), None);
+    }
+}
@@ -0,0 +1,286 @@
+//! Tax-lot report, replayed from the buys, sells and fixed reconciliations
+//! of the action log with the configured cost basis method.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::utils::get_cur_timestamp;
+use crate::actions::Action;
+use crate::config::Config;
+use crate::constant::*;
+use crate::cost_basis::{CostBasis, Lot};
+use crate::portfolio::OneShot;
+
+#[derive(Debug, Clone)]
+struct TaxLot {
+    acquired_at: u64,
+    shot: OneShot,
+}
+
+impl Lot for TaxLot {
+    fn shot(&self) -> &OneShot {
+        &self.shot
+    }
+
+    fn shot_mut(&mut self) -> &mut OneShot {
+        &mut self.shot
+    }
+}
+
+/// A lot, or the part of it disposed by a sale. Amounts are raw tokens and
+/// lamports, times are UNIX timestamps in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaxLotRow {
+    pub token: String,
+    /// Unset for tokens sold beyond the known lots, such as minted JIMMY
+    pub acquired_at: Option<u64>,
+    pub quantity: u64,
+    pub cost: f64,
+    /// Unset for lots still held
+    pub disposed_at: Option<u64>,
+    pub proceeds: Option<f64>,
+    /// Seconds from acquisition to disposal, or to now for lots still held
+    pub holding_period: Option<u64>,
+}
+
+impl TaxLotRow {
+    pub fn gain(&self) -> Option<f64> {
+        self.proceeds.map(|proceeds| proceeds - self.cost)
+    }
+}
+
+fn acquire(
+    lots: &mut HashMap<String, Vec<TaxLot>>,
+    token: &str,
+    quantity: u64,
+    cost: u64,
+    timestamp: u64,
+) {
+    lots.entry(token.to_string()).or_default().push(TaxLot {
+        acquired_at: timestamp,
+        shot: OneShot {
+            quantity,
+            total_cost_in_sol: cost as f64,
+        },
+    });
+}
+
+/// Takes a sale of `amount` tokens for `sol` lamports out of the lots.
+fn dispose(
+    lots: &mut HashMap<String, Vec<TaxLot>>,
+    cost_basis: CostBasis,
+    token: &str,
+    amount: u64,
+    sol: u64,
+    timestamp: u64,
+) -> Vec<TaxLotRow> {
+    let proceeds = |quantity: u64| sol as f64 * quantity as f64 / amount as f64;
+    let token_lots = lots.entry(token.to_string()).or_default();
+
+    let mut remaining = amount;
+    let mut rows = vec![];
+    for (lot, taken) in cost_basis.take(token_lots, amount) {
+        remaining -= taken.quantity;
+        rows.push(TaxLotRow {
+            token: token.to_string(),
+            acquired_at: Some(lot.acquired_at),
+            quantity: taken.quantity,
+            cost: taken.total_cost_in_sol,
+            disposed_at: Some(timestamp),
+            proceeds: Some(proceeds(taken.quantity)),
+            holding_period: Some(timestamp.saturating_sub(lot.acquired_at)),
+        });
+    }
+    if remaining > 0 {
+        rows.push(TaxLotRow {
+            token: token.to_string(),
+            acquired_at: None,
+            quantity: remaining,
+            cost: 0.0,
+            disposed_at: Some(timestamp),
+            proceeds: Some(proceeds(remaining)),
+            holding_period: None,
+        });
+    }
+
+    rows
+}
+
+/// Replays the portfolio actions, with their timestamps in order, into the
+/// disposed lots followed by the lots still held.
+pub fn tax_lots(
+    actions: impl IntoIterator<Item = (PortfolioAction, u64)>,
+    cost_basis: CostBasis,
+    now: u64,
+) -> Vec<TaxLotRow> {
+    let mut lots: HashMap<String, Vec<TaxLot>> = HashMap::new();
+    let mut rows = vec![];
+
+    for (action, timestamp) in actions {
+        match action {
+            // Fees are part of the cost and come off the proceeds
+            PortfolioAction::Buy {
+                token,
+                amount,
+                sol,
+                fill,
+                ..
+            } => {
+                let cost = fill.map(|fill| fill.net_sol).unwrap_or(sol);
+                acquire(&mut lots, &token, amount, cost, timestamp)
+            }
+            PortfolioAction::Sell {
+                token,
+                amount,
+                sol,
+                fill,
+                ..
+            } => {
+                let proceeds = fill.map(|fill| fill.net_sol).unwrap_or(sol);
+                rows.extend(dispose(
+                    &mut lots, cost_basis, &token, amount, proceeds, timestamp,
+                ))
+            }
+            // Fixed drifts moved tokens in at no cost or out for nothing
+            PortfolioAction::Reconcile {
+                drifts,
+                fixed: true,
+                ..
+            } => {
+                for drift in drifts {
+                    if drift.on_chain > drift.stored {
+                        let quantity = drift.on_chain - drift.stored;
+                        acquire(&mut lots, &drift.token, quantity, 0, timestamp);
+                    } else {
+                        let quantity = drift.stored - drift.on_chain;
+                        rows.extend(dispose(
+                            &mut lots,
+                            cost_basis,
+                            &drift.token,
+                            quantity,
+                            0,
+                            timestamp,
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut open = lots.into_iter().collect::<Vec<_>>();
+    open.sort_by(|a, b| a.0.cmp(&b.0));
+    for (token, token_lots) in open {
+        for lot in token_lots {
+            rows.push(TaxLotRow {
+                token: token.clone(),
+                acquired_at: Some(lot.acquired_at),
+                quantity: lot.shot.quantity,
+                cost: lot.shot.total_cost_in_sol,
+                disposed_at: None,
+                proceeds: None,
+                holding_period: Some(now.saturating_sub(lot.acquired_at)),
+            });
+        }
+    }
+
+    rows
+}
+
+/// CSV of the rows, with SOL amounts and holding periods in days.
+pub fn to_csv(rows: &[TaxLotRow]) -> String {
+    fn optional<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+    let sol = |lamports: f64| format!("{:.9}", lamports / LAMPORTS_PER_SOL as f64);
+    let days = |secs: u64| format!("{:.2}", secs as f64 / (60 * 60 * 24) as f64);
+
+    let mut csv = String::from(
+        "token,acquired_at,disposed_at,quantity,cost_sol,proceeds_sol,gain_sol,holding_period_days\n",
+    );
+    for row in rows {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{}",
+            row.token,
+            optional(row.acquired_at),
+            optional(row.disposed_at),
+            row.quantity,
+            sol(row.cost),
+            optional(row.proceeds.map(sol)),
+            optional(row.gain().map(sol)),
+            optional(row.holding_period.map(days)),
+        );
+    }
+    csv
+}
+
+/// Writes the tax-lot report of the action log as CSV to `path`, or prints it.
+pub fn export(path: Option<String>) -> Result<()> {
+    let mut actions = PortfolioAction::iter()
+        .map(|(action, raw)| (action, raw.timestamp()))
+        .collect::<Vec<_>>();
+    actions.sort_by_key(|(_, timestamp)| *timestamp);
+
+    let rows = tax_lots(actions, Config::get().cost_basis, get_cur_timestamp());
+    let csv = to_csv(&rows);
+    match path {
+        Some(path) => {
+            std::fs::write(&path, csv)?;
+            tracing::info!("Wrote {} tax lots to {}", rows.len(), path);
+        }
+        None => print!("{}", csv),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy(amount: u64, sol: u64, timestamp: u64) -> (PortfolioAction, u64) {
+        let action = PortfolioAction::buy("TOKEN".into(), amount, sol, String::new());
+        (action, timestamp)
+    }
+
+    fn sell(amount: u64, sol: u64, timestamp: u64) -> (PortfolioAction, u64) {
+        let action = PortfolioAction::sell("TOKEN".into(), amount, sol, String::new());
+        (action, timestamp)
+    }
+
+    #[test]
+    fn test_fifo_lots() {
+        let actions = vec![buy(100, 100, 0), buy(100, 300, 10), sell(150, 600, 20)];
+        let rows = tax_lots(actions, CostBasis::Fifo, 100);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].acquired_at, Some(0));
+        assert_eq!(rows[0].gain(), Some(300.0));
+        assert_eq!(rows[0].holding_period, Some(20));
+        assert_eq!((rows[1].quantity, rows[1].cost), (50, 150.0));
+        assert_eq!(rows[1].proceeds, Some(200.0));
+        // The rest of the second lot is still held
+        assert_eq!(rows[2].disposed_at, None);
+        assert_eq!(rows[2].holding_period, Some(90));
+
+        let csv = to_csv(&rows);
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv
+            .lines()
+            .nth(3)
+            .unwrap()
+            .starts_with("TOKEN,10,,50,0.000000150,,,"));
+    }
+
+    #[test]
+    fn test_sell_beyond_lots() {
+        let rows = tax_lots(vec![sell(10, 50, 5)], CostBasis::Lifo, 10);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].acquired_at, None);
+        assert_eq!(rows[0].gain(), Some(50.0));
+    }
+}
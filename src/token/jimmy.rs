@@ -9,6 +9,7 @@ use solana_sdk::{
     instruction::Instruction,
     program_pack::Pack,
     pubkey::Pubkey,
+    signature::Signature,
     signer::{keypair::Keypair, Signer},
     system_instruction,
     transaction::Transaction,
@@ -21,6 +22,7 @@ use super::raydium::{create_position, create_raydium_clmm_pool};
 use crate::client::get_finalized_client;
 use crate::config::Config;
 use crate::constant::*;
+use crate::paper::PaperWallet;
 use crate::store::{LocalStore, Store};
 use crate::token::utils::get_metadata;
 use crate::wallet::Wallet;
@@ -68,17 +70,28 @@ impl JimmyToken {
 
     async fn recover_or_launch(wallet: &Wallet) -> Result<Self> {
         const KEY_NAME: &str = "JimmyToken";
+        const PAPER_KEY_NAME: &str = "PaperJimmyToken";
+
+        // Paper trading never launches the token nor its pool: the launched
+        // token if any, else a mint that only exists on paper
+        if Config::get().paper_trading {
+            if let Some(jimmy_token) = Self::recover(KEY_NAME)? {
+                return Ok(jimmy_token);
+            }
+            if let Some(jimmy_token) = Self::recover(PAPER_KEY_NAME)? {
+                return Ok(jimmy_token);
+            }
+            let jimmy_token = Self::paper(wallet);
+            jimmy_token.save(PAPER_KEY_NAME)?;
+            tracing::info!("Paper JimmyToken created, never launched");
+            return Ok(jimmy_token);
+        }
 
-        let mut jimmy_token = if let Some(value) = LocalStore::get(KEY_NAME.as_bytes())? {
-            let jimmy_token_data: JimmyTokenData = bincode::deserialize(&value)?;
-            let jimmy_token = JimmyToken::try_from(jimmy_token_data)?;
-            tracing::info!("JimmyToken recovered from store");
+        let mut jimmy_token = if let Some(jimmy_token) = Self::recover(KEY_NAME)? {
             jimmy_token
         } else {
             let jimmy_token = Self::launch(wallet)?;
-            let jimmy_token_data: JimmyTokenData = (&jimmy_token).into();
-            let value = bincode::serialize(&jimmy_token_data)?;
-            LocalStore::put(KEY_NAME.as_bytes(), &value)?;
+            jimmy_token.save(KEY_NAME)?;
             tracing::info!("JimmyToken launched and saved to store");
             jimmy_token
         };
@@ -102,14 +115,43 @@ impl JimmyToken {
             let upper_price = config.raydium_pool_max_price;
             create_position(&jimmy_token, sol_amount, lower_price, upper_price)?;
 
-            let jimmy_token_data: JimmyTokenData = (&jimmy_token).into();
-            let value = bincode::serialize(&jimmy_token_data)?;
-            LocalStore::put(KEY_NAME.as_bytes(), &value)?;
+            jimmy_token.save(KEY_NAME)?;
         }
 
         Ok(jimmy_token)
     }
 
+    fn recover(key_name: &str) -> Result<Option<Self>> {
+        let Some(value) = LocalStore::get(key_name.as_bytes())? else {
+            return Ok(None);
+        };
+        let jimmy_token_data: JimmyTokenData = bincode::deserialize(&value)?;
+        let jimmy_token = JimmyToken::try_from(jimmy_token_data)?;
+        tracing::info!("{} recovered from store", key_name);
+        Ok(Some(jimmy_token))
+    }
+
+    fn save(&self, key_name: &str) -> Result<()> {
+        let jimmy_token_data: JimmyTokenData = self.into();
+        let value = bincode::serialize(&jimmy_token_data)?;
+        LocalStore::put(key_name.as_bytes(), &value)
+    }
+
+    /// A token of a new mint, without a pool, that is never sent on chain.
+    fn paper(wallet: &Wallet) -> Self {
+        let mint = Keypair::new();
+        let owner_token_account = spl_associated_token_account::get_associated_token_address(
+            &wallet.pubkey(),
+            &mint.pubkey(),
+        );
+        Self {
+            mint,
+            owner_token_account,
+            wallet_pubkey: wallet.pubkey(),
+            raydium_pool_id: None,
+        }
+    }
+
     fn launch(wallet: &Wallet) -> Result<Self> {
         let mint = Keypair::new();
         let owner_token_account = spl_associated_token_account::get_associated_token_address(
@@ -248,6 +290,47 @@ impl JimmyToken {
         anyhow::bail!("Failed to launch JIMMY token")
     }
 
+    /// Burns JIMMY from the token account of the wallet.
+    pub fn burn(&self, amount: u64) -> Result<Signature> {
+        if Config::get().paper_trading {
+            return PaperWallet::get().burn(&self.mint.pubkey(), amount);
+        }
+
+        let wallet = Wallet::get();
+        let client = get_finalized_client();
+
+        let burn_ix = spl_token::instruction::burn(
+            &spl_token::id(),
+            &self.owner_token_account,
+            &self.mint.pubkey(),
+            &self.wallet_pubkey,
+            &[],
+            amount,
+        )?;
+        let priority_ix = ComputeBudgetInstruction::set_compute_unit_price(COMPUTE_UNIT_PRICE);
+
+        let recent_blockhash = client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[priority_ix, burn_ix],
+            Some(&self.wallet_pubkey),
+            &[wallet.keypair()],
+            recent_blockhash,
+        );
+
+        let signature = client.send_and_confirm_transaction_with_spinner_and_config(
+            &transaction,
+            CommitmentConfig::finalized(),
+            RpcSendTransactionConfig {
+                skip_preflight: SKIP_PREFLIGHT,
+                max_retries: Some(INNER_MAX_RETRIES),
+                ..RpcSendTransactionConfig::default()
+            },
+        )?;
+        tracing::info!("🔥 Burned {} JIMMY: {}", amount, signature);
+
+        Ok(signature)
+    }
+
     pub fn print_balance(&self) -> Result<()> {
         let client = get_finalized_client();
 
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use serde_json::Value;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::OnceLock;
 
@@ -10,7 +11,10 @@ use crate::store::{LocalStore, Store};
 use super::structs::TokenInfo;
 
 pub struct SolanaTokenStore {
-    tokens: StoreMap<String, TokenInfo, LocalStore>,
+    /// Tokens by mint
+    tokens: StoreMap<Pubkey, TokenInfo, LocalStore>,
+    /// Mints by symbol, several mints may share a symbol
+    symbols: StoreMap<String, Vec<Pubkey>, LocalStore>,
 }
 
 /*
@@ -38,22 +42,69 @@ pub struct SolanaTokenStore {
 ]
 */
 impl SolanaTokenStore {
-    const TOKENS_PREFIX: &'static str = "solana_tokens";
+    const TOKENS_PREFIX: &'static str = "solana_mints";
+    const SYMBOLS_PREFIX: &'static str = "solana_symbols";
+    /// Tokens keyed by symbol, migrated to the mint keys
+    const LEGACY_TOKENS_PREFIX: &'static str = "solana_tokens";
+
     pub fn get() -> &'static Self {
         static INSTANCE: OnceLock<SolanaTokenStore> = OnceLock::new();
-        INSTANCE.get_or_init(|| SolanaTokenStore::new())
+        INSTANCE.get_or_init(|| {
+            let store = SolanaTokenStore::new();
+            if let Err(e) = store.migrate_symbol_keys() {
+                tracing::error!("Failed to migrate the token store: {}", e);
+            }
+            store
+        })
     }
 
     fn new() -> Self {
         Self {
             tokens: LocalStore::open_map(Self::TOKENS_PREFIX),
+            symbols: LocalStore::open_map(Self::SYMBOLS_PREFIX),
+        }
+    }
+
+    /// Moves the tokens keyed by symbol under their mint.
+    fn migrate_symbol_keys(&self) -> Result<()> {
+        let legacy: StoreMap<String, TokenInfo, LocalStore> =
+            LocalStore::open_map(Self::LEGACY_TOKENS_PREFIX);
+        let count = legacy.drain_into(|_, token_info| self.insert_all([token_info]))?;
+        if count > 0 {
+            tracing::info!("Migrated {} tokens to mint keys", count);
         }
+
+        Ok(())
     }
 
-    pub fn tokens(&self) -> &StoreMap<String, TokenInfo, LocalStore> {
+    pub fn tokens(&self) -> &StoreMap<Pubkey, TokenInfo, LocalStore> {
         &self.tokens
     }
 
+    /// Inserts the tokens and indexes them by symbol.
+    fn insert_all(&self, tokens: impl IntoIterator<Item = TokenInfo>) -> Result<()> {
+        let mut symbols: HashMap<String, Vec<Pubkey>> = HashMap::new();
+        for token_info in tokens {
+            let mints = symbols.entry(token_info.symbol.clone()).or_insert_with(|| {
+                self.symbols
+                    .get(&token_info.symbol)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default()
+            });
+            if !mints.contains(&token_info.address) {
+                mints.push(token_info.address);
+            }
+            self.tokens.insert(token_info.address, token_info)?;
+        }
+
+        for (symbol, mints) in symbols {
+            self.symbols.insert(symbol, mints)?;
+        }
+
+        Ok(())
+    }
+
     pub fn get_solana_symbol(&self, symbol: &str) -> String {
         match symbol {
             "BTC" => "WBTC".to_string(),
@@ -70,14 +121,54 @@ impl SolanaTokenStore {
         }
     }
 
+    /// Token of a symbol or a mint address, `None` if unknown or if several
+    /// mints share the symbol.
     pub async fn get_token_info(&self, symbol: &str) -> Result<Option<TokenInfo>> {
-        let need_update = self.check_tokens_is_empty();
-        if need_update {
+        if let Ok(mint) = Pubkey::from_str(symbol) {
+            return self.get_token_info_by_mint(&mint).await;
+        }
+
+        let mut tokens = self.get_tokens_by_symbol(symbol).await?;
+        match tokens.len() {
+            0 | 1 => Ok(tokens.pop()),
+            _ => {
+                let mints = tokens
+                    .iter()
+                    .map(|t| t.address.to_string())
+                    .collect::<Vec<_>>();
+                tracing::warn!(
+                    "Ambiguous symbol {} shared by {}, ignore it",
+                    symbol,
+                    mints.join(", ")
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    pub async fn get_token_info_by_mint(&self, mint: &Pubkey) -> Result<Option<TokenInfo>> {
+        if self.check_tokens_is_empty() {
+            self.update_tokens().await?;
+        }
+
+        self.tokens.get(mint)
+    }
+
+    /// Tokens of every mint sharing the symbol.
+    pub async fn get_tokens_by_symbol(&self, symbol: &str) -> Result<Vec<TokenInfo>> {
+        if self.check_tokens_is_empty() {
             self.update_tokens().await?;
         }
 
         let symbol = self.get_solana_symbol(symbol);
-        self.tokens.get(&symbol)
+        let mut tokens = vec![];
+        for mint in self.symbols.get(&symbol)?.unwrap_or_default() {
+            if let Some(token_info) = self.tokens.get(&mint)? {
+                tokens.push(token_info);
+            }
+        }
+
+        Ok(tokens)
     }
 
     async fn update_tokens(&self) -> Result<()> {
@@ -87,6 +178,7 @@ impl SolanaTokenStore {
         let json: Value = response.json().await?;
 
         if let Some(tokens) = json.as_array() {
+            let mut token_infos = vec![];
             for token in tokens {
                 let address = token
                     .get("address")
@@ -120,10 +212,9 @@ impl SolanaTokenStore {
                     coingecko_id: coingecko_id,
                 };
 
-                if let Err(e) = self.tokens.insert(symbol.to_string(), token_info) {
-                    return Err(e);
-                }
+                token_infos.push(token_info);
             }
+            self.insert_all(token_infos)?;
         } else {
             return Err(anyhow::anyhow!("No tokens found"));
         }
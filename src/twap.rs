@@ -0,0 +1,359 @@
+//! TWAP execution of the buys too large for one swap: a parent order is
+//! split into slices spread evenly over a time window, each quoted and
+//! checked against the price impact limit when it comes due. The first slice
+//! is sent with the round scheduling the order, and the later ones are
+//! attached to its record.
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::actions::portfolio::PortfolioAction;
+use crate::actions::utils::get_cur_timestamp;
+use crate::actions::Action;
+use crate::config::Config;
+use crate::constant::*;
+use crate::paper::trading_prefix;
+use crate::portfolio::Portfolio;
+use crate::round::RoundRecords;
+use crate::store::{LocalStore, Record, RecordMap};
+use crate::token::structs::TokenInfo;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SliceStatus {
+    Pending,
+    Filled,
+    Failed { reason: String },
+}
+
+/// A child order of a [`TwapOrder`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TwapSlice {
+    /// UNIX timestamp in seconds the slice is sent at
+    pub due_at: u64,
+    pub sol_amount: u64,
+    pub status: SliceStatus,
+}
+
+/// A parent order buying a token with `sol_amount` lamports in slices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwapOrder {
+    pub id: u64,
+    pub token_info: TokenInfo,
+    pub sol_amount: u64,
+    pub started_at: u64,
+    pub window_secs: u64,
+    pub slices: Vec<TwapSlice>,
+    /// Round the order was scheduled by
+    #[serde(default)]
+    pub round_id: Option<u64>,
+}
+
+impl TwapOrder {
+    /// Splits `sol_amount` into `slices` equal slices, the first due at
+    /// `started_at` and the last within the window. The remainder of the
+    /// division goes to the last slice.
+    pub fn new(
+        id: u64,
+        token_info: &TokenInfo,
+        sol_amount: u64,
+        slices: usize,
+        window_secs: u64,
+        started_at: u64,
+    ) -> Self {
+        let count = slices.max(1) as u64;
+        let slices = (0..count)
+            .map(|i| TwapSlice {
+                due_at: started_at + window_secs * i / count,
+                sol_amount: if i == count - 1 {
+                    sol_amount - sol_amount / count * (count - 1)
+                } else {
+                    sol_amount / count
+                },
+                status: SliceStatus::Pending,
+            })
+            .collect();
+
+        Self {
+            id,
+            token_info: token_info.clone(),
+            sol_amount,
+            started_at,
+            window_secs,
+            slices,
+            round_id: None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.slices
+            .iter()
+            .all(|slice| slice.status != SliceStatus::Pending)
+    }
+
+    /// Lamports of the slices still pending.
+    pub fn pending_sol(&self) -> u64 {
+        self.slices
+            .iter()
+            .filter(|slice| slice.status == SliceStatus::Pending)
+            .map(|slice| slice.sol_amount)
+            .sum()
+    }
+
+    /// Index of the earliest pending slice due at `now`.
+    pub fn next_due(&self, now: u64) -> Option<usize> {
+        self.slices
+            .iter()
+            .position(|slice| slice.status == SliceStatus::Pending)
+            .filter(|i| self.slices[*i].due_at <= now)
+    }
+}
+
+/// Number of slices of a buy, at most the configured count and never
+/// smaller than the minimum slice size.
+#[derive(Debug, Clone)]
+pub struct TwapPlan {
+    pub slices: usize,
+    pub window_secs: u64,
+    /// Smallest slice worth its fees, in lamports
+    pub min_slice_lamports: u64,
+}
+
+impl TwapPlan {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            slices: config.twap_slices,
+            window_secs: config.twap_window_secs,
+            min_slice_lamports: (config.twap_min_slice_sol * LAMPORTS_PER_SOL as f64) as u64,
+        }
+    }
+
+    pub fn slice_count(&self, sol_amount: u64) -> usize {
+        let max_slices = match self.min_slice_lamports {
+            0 => self.slices,
+            min => (sol_amount / min) as usize,
+        };
+        self.slices.min(max_slices).max(1)
+    }
+}
+
+impl Record for TwapOrder {
+    fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+pub struct TwapScheduler {
+    plan: TwapPlan,
+    orders: RecordMap<TwapOrder, LocalStore>,
+}
+
+impl TwapScheduler {
+    const TWAP_ORDERS_PREFIX: &'static str = "twap_orders";
+
+    pub fn get() -> &'static Self {
+        static INSTANCE: OnceLock<TwapScheduler> = OnceLock::new();
+        INSTANCE.get_or_init(Self::new)
+    }
+
+    fn new() -> Self {
+        Self {
+            plan: TwapPlan::from_config(Config::get()),
+            orders: RecordMap::new(trading_prefix(Self::TWAP_ORDERS_PREFIX)),
+        }
+    }
+
+    fn save(&self, order: &TwapOrder) -> Result<()> {
+        self.orders.save(order)
+    }
+
+    pub fn plan(&self) -> &TwapPlan {
+        &self.plan
+    }
+
+    /// Schedules a buy of `sol_amount` lamports in `slices` slices over the
+    /// configured window for the round `round_id`, and sends the first slice.
+    pub async fn schedule(
+        &self,
+        token_info: &TokenInfo,
+        sol_amount: u64,
+        slices: usize,
+        round_id: u64,
+    ) -> Result<TwapOrder> {
+        let mut order = TwapOrder::new(
+            self.orders.next_id()?,
+            token_info,
+            sol_amount,
+            slices,
+            self.plan.window_secs,
+            get_cur_timestamp(),
+        );
+        order.round_id = Some(round_id);
+        self.save(&order)?;
+
+        tracing::info!(
+            "Scheduled TWAP order {}: {} lamports of {} in {} slices over {} seconds",
+            order.id,
+            order.sol_amount,
+            token_info.symbol,
+            order.slices.len(),
+            order.window_secs
+        );
+        PortfolioAction::twap(
+            token_info.address.to_string(),
+            order.id,
+            order.sol_amount,
+            order.slices.len(),
+            order.window_secs,
+            order.round_id,
+        )
+        .log();
+
+        // Due now, sent within the round so its record holds the swap
+        self.send_slice(&mut order, 0).await?;
+
+        Ok(order)
+    }
+
+    /// All orders, oldest first.
+    pub fn list(&self) -> Vec<TwapOrder> {
+        self.orders.list()
+    }
+
+    /// Lamports of the pending slices of the unfinished orders, by mint.
+    pub fn committed(&self) -> HashMap<Pubkey, u64> {
+        let mut committed = HashMap::new();
+        for order in self.list().into_iter().filter(|order| !order.is_done()) {
+            *committed.entry(order.token_info.address).or_default() += order.pending_sol();
+        }
+        committed
+    }
+
+    /// Sends the slice coming due of every unfinished order, one per order
+    /// so slices missed while stopped are not sent all at once.
+    pub async fn check(&self) -> Result<()> {
+        let now = get_cur_timestamp();
+
+        for mut order in self.list().into_iter().filter(|order| !order.is_done()) {
+            let Some(index) = order.next_due(now) else {
+                continue;
+            };
+            self.send_slice(&mut order, index).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the slice `index` of the order, attaching it to the record of
+    /// its round once the round is over.
+    async fn send_slice(&self, order: &mut TwapOrder, index: usize) -> Result<()> {
+        // Quoted and checked against the price impact limit on its own
+        let sol_amount = order.slices[index].sol_amount;
+        let bought = Portfolio::get()
+            .await
+            .buy_token(&order.token_info, sol_amount)
+            .await;
+        let error = bought.as_ref().err().map(|e| e.to_string());
+        order.slices[index].status = match &error {
+            None => SliceStatus::Filled,
+            Some(reason) => {
+                tracing::error!(
+                    "Failed to send slice {} of TWAP order {}: {}",
+                    index,
+                    order.id,
+                    reason
+                );
+                SliceStatus::Failed {
+                    reason: reason.clone(),
+                }
+            }
+        };
+        self.save(order)?;
+
+        let action = PortfolioAction::twap_slice(
+            order.token_info.address.to_string(),
+            order.id,
+            index,
+            sol_amount,
+            bought.ok().map(|sig| sig.to_string()),
+            error,
+            order.round_id,
+        );
+        action.log();
+        if let Some(round_id) = order.round_id {
+            RoundRecords::get().attach(round_id, action)?;
+        }
+
+        if order.is_done() {
+            let filled = order
+                .slices
+                .iter()
+                .filter(|slice| slice.status == SliceStatus::Filled)
+                .count();
+            tracing::info!(
+                "TWAP order {} of {} done, {}/{} slices filled",
+                order.id,
+                order.token_info.symbol,
+                filled,
+                order.slices.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_twap_slices() {
+        let token_info = TokenInfo {
+            address: Pubkey::new_unique(),
+            decimals: 6,
+            name: "Test".to_string(),
+            symbol: "TEST".to_string(),
+            coingecko_id: None,
+        };
+        let mut order = TwapOrder::new(0, &token_info, 1_000_000_001, 4, 3_600, 1_000);
+        let amounts = order
+            .slices
+            .iter()
+            .map(|slice| (slice.due_at, slice.sol_amount))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            amounts,
+            vec![
+                (1_000, 250_000_000),
+                (1_900, 250_000_000),
+                (2_800, 250_000_000),
+                (3_700, 250_000_001)
+            ]
+        );
+
+        assert_eq!(order.next_due(1_000), Some(0));
+        order.slices[0].status = SliceStatus::Filled;
+        assert_eq!(order.next_due(1_899), None);
+        order.slices[1].status = SliceStatus::Failed {
+            reason: "Quote rejected".to_string(),
+        };
+        // Missed slices are sent one by one
+        assert_eq!(order.next_due(10_000), Some(2));
+        order.slices[2].status = SliceStatus::Filled;
+        order.slices[3].status = SliceStatus::Filled;
+        assert!(order.is_done());
+
+        let plan = TwapPlan {
+            slices: 4,
+            window_secs: 3_600,
+            min_slice_lamports: LAMPORTS_PER_SOL / 10,
+        };
+        assert_eq!(plan.slice_count(LAMPORTS_PER_SOL), 4);
+        assert_eq!(plan.slice_count(LAMPORTS_PER_SOL / 4), 2);
+        assert_eq!(plan.slice_count(LAMPORTS_PER_SOL / 20), 1);
+    }
+}
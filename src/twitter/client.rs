@@ -63,7 +63,8 @@ impl TwitterClient {
             &config.twitter_consumer_key_secret,
             &config.twitter_access_token_secret,
         );
-        let use_twitter = config.use_twitter;
+        // Paper trades are never announced on the real account
+        let use_twitter = config.use_twitter && !config.paper_trading;
 
         Self {
             client,